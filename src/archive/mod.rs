@@ -1,7 +1,11 @@
-use std::fs;
-use std::path::{PathBuf};
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use flate2::read::GzDecoder;
 use crate::archive::utils::{ArchiveBuilder, ArchiveEntry};
 
+pub(crate) use crate::archive::utils::EntryVisitor;
+
 mod sevenz;
 mod tgz;
 mod zip;
@@ -46,6 +50,30 @@ impl UnpackImpl {
         }
     }
 
+    /// 依次流式读取归档中的每个文件而不写入磁盘 (供 `Commands::Hash` 计算各条目的摘要), 扩展名不区分大小写
+    /// 与解包不同, '*.tar.gz' 会直接读取其中的 tar 条目; 返回因路径不安全而跳过的条目
+    pub(crate) fn read_files(source: &str, visit: &mut EntryVisitor) -> Result<Vec<String>, String> {
+        let path = Path::new(source);
+        let name = path.file_name().map_or(String::new(), |name| name.to_string_lossy().to_lowercase());
+        let file = File::open(path).map_err(|err| format!("{err}"))?;
+
+        if name.ends_with(".zip") {
+            zip::read_files(BufReader::new(file), visit)
+        } else if name.ends_with(".7z") {
+            let len = file.metadata().map_err(|err| format!("{err}"))?.len();
+            sevenz::read_files(BufReader::new(file), len, visit)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            tgz::read_tar(GzDecoder::new(BufReader::new(file)), visit)
+        } else if name.ends_with(".tar") {
+            tgz::read_tar(BufReader::new(file), visit)
+        } else if name.ends_with(".gz") {
+            let stem = path.file_stem().map_or(String::new(), |stem| stem.to_string_lossy().to_string());
+            tgz::read_gz(BufReader::new(file), &stem, visit)
+        } else {
+            Err("Invalid format".to_string())
+        }
+    }
+
     pub fn handle(source: String, destination: String) {
         let suffix = source.split(".").last().unwrap_or("").to_string();
         println!("[Commands::Unpack] source = '{source}', destination = '{destination}', suffix = '{suffix}'");
@@ -1,8 +1,8 @@
 use std::cmp::Ordering;
-use std::io::{Cursor};
+use std::io::{Cursor, Read, Seek};
 use std::path::{PathBuf};
 use sevenz_rust::{Password, SevenZArchiveEntry};
-use crate::archive::utils::ArchiveEntry;
+use crate::archive::utils::{enclosed_name, ArchiveEntry, EntryVisitor};
 
 /// 将 7z 解包为 `ArchiveEntry` 列表, 返回 `ArchiveEntry` 列表 (按照文件夹优先, 文件次之的顺序排序)
 pub fn unpack(binary: Vec<u8>, _source_stem: String, disk_root: String) -> Vec<ArchiveEntry> {
@@ -47,6 +47,40 @@ pub fn unpack(binary: Vec<u8>, _source_stem: String, disk_root: String) -> Vec<A
     entries
 }
 
+/// 依次流式读取 7z 中的每个文件 (不会将整个归档或解压后的文件读入内存), `len` 为归档的大小
+/// 路径不安全 (见 `enclosed_name`) 的条目会被跳过, 返回这些条目的原始名称
+pub fn read_files(reader: impl Read + Seek, len: u64, visit: &mut EntryVisitor) -> Result<Vec<String>, String> {
+    let mut archive = sevenz_rust::SevenZReader::new(reader, len, Password::empty()).map_err(|err| format!("{err}"))?;
+    let mut skipped = vec![];
+
+    // 回调中的错误需要转换为 sevenz_rust 的错误才能中断遍历, 因此先暂存下来
+    let mut visit_err = None;
+    let result = archive.for_each_entries(|entry, reader| {
+        if entry.is_directory {
+            return Ok(true);
+        }
+        let pack_dir = match enclosed_name(entry.name()) {
+            Some(pack_dir) => pack_dir,
+            None => {
+                skipped.push(entry.name().to_string());
+                return Ok(true);
+            }
+        };
+        match visit(&pack_dir, reader) {
+            Ok(_) => Ok(true),
+            Err(err) => {
+                visit_err = Some(err);
+                Ok(false)
+            }
+        }
+    });
+
+    match visit_err {
+        Some(err) => Err(err),
+        None => result.map(|_| skipped).map_err(|err| format!("{err}")),
+    }
+}
+
 /// 将 `ArchiveEntry` 列表打包为 7z, 返回二进制数据
 pub fn pack(entries: Vec<ArchiveEntry>, _filename: String) -> Vec<u8> {
     let mut bundle = sevenz_rust::SevenZWriter::new(Cursor::new(vec![])).unwrap();
@@ -87,6 +121,24 @@ mod unit_test {
         }
     }
 
+    /// read_files 函数测试: 在内存中打包后流式读取
+    #[test]
+    fn read_files_test() {
+        let entry = ArchiveEntry::in_memory;
+        let package = pack(vec![entry("dir", None), entry("dir/a.txt", Some(b"hello")), entry("b.txt", Some(b"world")), entry("/etc/evil.txt", Some(b"evil"))], "".to_string());
+
+        let mut files = vec![];
+        let len = package.len() as u64;
+        let skipped = read_files(Cursor::new(package), len, &mut |path, reader| {
+            let mut raw = vec![];
+            reader.read_to_end(&mut raw).map_err(|err| format!("{err}"))?;
+            files.push((path.to_string(), raw));
+            Ok(())
+        }).unwrap();
+        assert_eq!(skipped, vec!["/etc/evil.txt".to_string()]);
+        assert_eq!(files, vec![("dir/a.txt".to_string(), b"hello".to_vec()), ("b.txt".to_string(), b"world".to_vec())]);
+    }
+
     /// pack 函数测试
     #[test]
     fn pack_test() {
@@ -3,7 +3,7 @@ use std::io::{Cursor, Read, Write};
 use std::path::{PathBuf};
 use flate2::{Compression, GzBuilder};
 use flate2::read::GzDecoder;
-use crate::archive::utils::ArchiveEntry;
+use crate::archive::utils::{enclosed_name, ArchiveEntry, EntryVisitor};
 
 /// 将 gz 解包为 `ArchiveEntry` 列表, 返回 `ArchiveEntry` 列表 (仅包含一项 `ArchiveEntry`)
 pub fn unpack_gz(binary: Vec<u8>, source_stem: String, disk_root: String) -> Vec<ArchiveEntry> {
@@ -33,6 +33,20 @@ pub fn unpack_gz(binary: Vec<u8>, source_stem: String, disk_root: String) -> Vec
     ]
 }
 
+/// 流式读取 gz 中的文件, 文件名取自 gz 头部, 没有时使用 `source_stem`
+/// 头部的文件名不安全 (见 `enclosed_name`) 时跳过, 返回该文件名
+pub fn read_gz(reader: impl Read, source_stem: &str, visit: &mut EntryVisitor) -> Result<Vec<String>, String> {
+    let mut archive = GzDecoder::new(reader);
+    let name = archive.header()
+        .and_then(|header| header.filename())
+        .map_or(source_stem.to_string(), |name| String::from_utf8_lossy(name).to_string());
+
+    match enclosed_name(&name) {
+        Some(pack_dir) => visit(&pack_dir, &mut archive).map(|_| vec![]),
+        None => Ok(vec![name]),
+    }
+}
+
 /// 将 `ArchiveEntry` 列表打包为 gz, 返回二进制数据 (仅压缩第一项文件, 若列表都是文件夹则返回空)
 pub fn pack_gz(entries: Vec<ArchiveEntry>, _filename: String) -> Vec<u8> {
     // 找到第一个文件, 作为压缩对象
@@ -93,6 +107,27 @@ pub fn unpack_tar(binary: Vec<u8>, _source_stem: String, disk_root: String) -> V
     entries
 }
 
+/// 依次流式读取 tar 中的每个文件 (tgz 传入 `GzDecoder` 即可), 不会将整个归档或解压后的文件读入内存
+/// 路径不安全 (见 `enclosed_name`) 的条目会被跳过, 返回这些条目的原始名称
+pub fn read_tar(reader: impl Read, visit: &mut EntryVisitor) -> Result<Vec<String>, String> {
+    let mut archive = tar::Archive::new(reader);
+    let mut skipped = vec![];
+
+    for item in archive.entries().map_err(|err| format!("{err}"))? {
+        let mut item = item.map_err(|err| format!("{err}"))?;
+        if !item.header().entry_type().is_file() {
+            continue;
+        }
+        let name = item.path().map_err(|err| format!("{err}"))?.to_string_lossy().to_string();
+        match enclosed_name(&name) {
+            Some(pack_dir) => visit(&pack_dir, &mut item)?,
+            None => skipped.push(name),
+        }
+    }
+
+    Ok(skipped)
+}
+
 /// 将 `ArchiveEntry` 列表打包为 tar, 返回二进制数据
 pub fn pack_tar(entries: Vec<ArchiveEntry>, _filename: String) -> Vec<u8> {
     let mut bundle = tar::Builder::new(Cursor::new(vec![]));
@@ -236,6 +271,33 @@ mod unit_test {
         }
     }
 
+    /// read_tar 函数测试: 在内存中打包后流式读取, 跳过路径不安全的条目
+    #[test]
+    fn read_tar_test() {
+        let entry = ArchiveEntry::in_memory;
+        let mut package = pack_tar(vec![entry("dir/a.txt", Some(b"hello")), entry("b.txt", Some(b"world"))], "".to_string());
+        // `tar::Header::set_path` 拒绝 '..', 直接写入头部的名称字段
+        let mut header = tar::Header::new_gnu();
+        header.as_old_mut().name[..12].copy_from_slice(b"../evil.txt\0");
+        header.set_size(4);
+        header.set_cksum();
+        let mut bundle = tar::Builder::new(vec![]);
+        bundle.append(&header, &b"evil"[..]).unwrap();
+        // 去掉第一个归档末尾的两个空块后拼接
+        package.truncate(package.len() - 1024);
+        package.extend(bundle.into_inner().unwrap());
+
+        let mut files = vec![];
+        let skipped = read_tar(Cursor::new(package), &mut |path, reader| {
+            let mut raw = vec![];
+            reader.read_to_end(&mut raw).map_err(|err| format!("{err}"))?;
+            files.push((path.to_string(), raw));
+            Ok(())
+        }).unwrap();
+        assert_eq!(skipped, vec!["../evil.txt".to_string()]);
+        assert_eq!(files, vec![("dir/a.txt".to_string(), b"hello".to_vec()), ("b.txt".to_string(), b"world".to_vec())]);
+    }
+
     /// pack 函数测试
     #[test]
     fn pack_tar_test() {
@@ -1,4 +1,5 @@
 use std::{fs};
+use std::io::Read;
 use std::path::{PathBuf};
 use walkdir::WalkDir;
 
@@ -35,6 +36,44 @@ pub struct ArchiveEntry {
     pub raw: Option<Vec<u8>>,
 }
 
+#[cfg(test)]
+impl ArchiveEntry {
+    /// 仅存在于内存中的条目 (测试用), `raw` 为 `None` 时为文件夹
+    pub fn in_memory(pack_dir: &str, raw: Option<&[u8]>) -> ArchiveEntry {
+        ArchiveEntry {
+            disk_dir: PathBuf::new(),
+            pack_dir: pack_dir.to_string(),
+            is_file: raw.is_some(),
+            is_dir: raw.is_none(),
+            raw: raw.map(|raw| raw.to_vec()),
+        }
+    }
+}
+
+/// 流式读取归档中的文件时, 对每个文件调用的回调: (条目路径, 文件内容)
+pub type EntryVisitor<'a> = dyn FnMut(&str, &mut dyn Read) -> Result<(), String> + 'a;
+
+/// 将归档条目的名称转换为安全的相对路径 (以 '/' 分隔, 去掉 '.'), 所有格式共用同一规则:
+/// 绝对路径 (含盘符)、含有 '..' (即使未越出归档根目录, 展开后也可能与其他条目重名)、含有 '\0' 或为空的名称不安全, 返回 `None`
+pub fn enclosed_name(name: &str) -> Option<String> {
+    let bytes = name.as_bytes();
+    let has_drive = bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':';
+    if name.contains('\0') || name.starts_with(['/', '\\']) || has_drive {
+        return None;
+    }
+
+    let mut parts = vec![];
+    for part in name.split(['/', '\\']) {
+        match part {
+            "" | "." => {}
+            ".." => return None,
+            _ => parts.push(part),
+        }
+    }
+
+    if parts.is_empty() { None } else { Some(parts.join("/")) }
+}
+
 // --------------------- ArchiveBuilder ---------------------
 pub struct ArchiveBuilder {
     /// 本地的根目录
@@ -168,4 +207,15 @@ mod unit_test {
             println!("ignore: {}", ignore);
         }
     }
+
+    /// enclosed_name 测试
+    #[test]
+    fn enclosed_name_test() {
+        assert_eq!(enclosed_name("dir/a.txt"), Some("dir/a.txt".to_string()));
+        assert_eq!(enclosed_name("./dir//./a.txt"), Some("dir/a.txt".to_string()));
+        assert_eq!(enclosed_name("dir\\a.txt"), Some("dir/a.txt".to_string()));
+        for name in ["dir/../a.txt", "../a.txt", "dir/../../a.txt", "/etc/passwd", "\\server\\a.txt", "C:/a.txt", "c:a.txt", "a\0.txt", "", "./"] {
+            assert_eq!(enclosed_name(name), None, "{name}");
+        }
+    }
 }
\ No newline at end of file
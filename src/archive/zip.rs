@@ -1,7 +1,7 @@
 use std::cmp::Ordering;
-use std::io::{Cursor, Read, Write};
+use std::io::{Cursor, Read, Seek, Write};
 use std::path::{PathBuf};
-use crate::archive::utils::ArchiveEntry;
+use crate::archive::utils::{enclosed_name, ArchiveEntry, EntryVisitor};
 
 /// 将 zip 解包为 `ArchiveEntry` 列表, 返回 `ArchiveEntry` 列表 (按照文件夹优先, 文件次之的顺序排序)
 pub fn unpack(binary: Vec<u8>, _source_stem: String, disk_root: String) -> Vec<ArchiveEntry> {
@@ -45,6 +45,26 @@ pub fn unpack(binary: Vec<u8>, _source_stem: String, disk_root: String) -> Vec<A
     entries
 }
 
+/// 依次流式读取 zip 中的每个文件 (不会将整个归档或解压后的文件读入内存)
+/// 路径不安全 (见 `enclosed_name`) 的条目会被跳过, 返回这些条目的原始名称
+pub fn read_files(reader: impl Read + Seek, visit: &mut EntryVisitor) -> Result<Vec<String>, String> {
+    let mut archive = zip::ZipArchive::new(reader).map_err(|err| format!("{err}"))?;
+    let mut skipped = vec![];
+
+    for idx in 0..archive.len() {
+        let mut file = archive.by_index(idx).map_err(|err| format!("{err}"))?;
+        if file.is_dir() {
+            continue;
+        }
+        match enclosed_name(file.name()) {
+            Some(pack_dir) => visit(&pack_dir, &mut file)?,
+            None => skipped.push(file.name().to_string()),
+        }
+    }
+
+    Ok(skipped)
+}
+
 /// 将 `ArchiveEntry` 列表打包为 zip, 返回二进制数据
 pub fn pack(entries: Vec<ArchiveEntry>, _filename: String) -> Vec<u8> {
     let mut bundle = zip::ZipWriter::new(Cursor::new(vec![]));
@@ -87,6 +107,23 @@ mod unit_test {
         }
     }
 
+    /// read_files 函数测试: 在内存中打包后流式读取
+    #[test]
+    fn read_files_test() {
+        let entry = ArchiveEntry::in_memory;
+        let package = pack(vec![entry("dir", None), entry("dir/a.txt", Some(b"hello")), entry("b.txt", Some(b"world")), entry("../evil.txt", Some(b"evil"))], "".to_string());
+
+        let mut files = vec![];
+        let skipped = read_files(Cursor::new(package), &mut |path, reader| {
+            let mut raw = vec![];
+            reader.read_to_end(&mut raw).map_err(|err| format!("{err}"))?;
+            files.push((path.to_string(), raw));
+            Ok(())
+        }).unwrap();
+        assert_eq!(skipped, vec!["../evil.txt".to_string()]);
+        assert_eq!(files, vec![("dir/a.txt".to_string(), b"hello".to_vec()), ("b.txt".to_string(), b"world".to_vec())]);
+    }
+
     /// pack 函数测试
     #[test]
     fn pack_test() {
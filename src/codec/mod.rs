@@ -0,0 +1,218 @@
+use std::fs;
+use std::io::{self, Read};
+use data_encoding::{Encoding, BASE32, BASE32_NOPAD, BASE64, BASE64URL, BASE64URL_NOPAD, BASE64_NOPAD, HEXLOWER, HEXLOWER_PERMISSIVE, HEXUPPER};
+use percent_encoding::{percent_decode, percent_encode, AsciiSet, NON_ALPHANUMERIC};
+
+/// 百分号编码时保留 RFC 3986 中的非保留字符 (字母, 数字及 '-', '.', '_', '~'), 其余字符都会被编码
+const URL_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'.').remove(b'_').remove(b'~');
+
+/// Arguments of `Commands::Encode` and `Commands::Decode`
+pub struct CodecOptions {
+    pub source: String,
+    pub filemode: bool,
+    pub encoding: String,
+    pub output: Option<String>,
+    /// 编码时是否省略填充 ('='), 仅用于 base64, base64url 与 base32
+    pub no_padding: bool,
+    pub decode: bool,
+}
+
+/// 支持的编码
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Codec {
+    /// 标准 base64
+    Base64,
+    /// URL 安全的 base64
+    Base64Url,
+    /// RFC 4648 base32
+    Base32,
+    /// base58 (Bitcoin 字母表)
+    Base58,
+    /// 小写十六进制
+    Hex,
+    /// 大写十六进制 (解码时与 `Hex` 相同)
+    HexUpper,
+    /// 百分号编码 (URL)
+    Url,
+    /// HTML 实体
+    Html,
+}
+
+impl Codec {
+    /// 解析 `--encoding` 参数 (不区分大小写, 与 `hash --encoding` 一致使用 'hex-upper' 表示大写十六进制)
+    fn parse(encoding: &str) -> Result<Codec, String> {
+        match encoding.to_lowercase().as_str() {
+            "hex" => Ok(Codec::Hex),
+            "hex-upper" => Ok(Codec::HexUpper),
+            "base64" => Ok(Codec::Base64),
+            "base64url" => Ok(Codec::Base64Url),
+            "base32" => Ok(Codec::Base32),
+            "base58" => Ok(Codec::Base58),
+            "url" => Ok(Codec::Url),
+            "html" => Ok(Codec::Html),
+            _ => Err(format!("Invalid encoding '{encoding}' (Expect one of: base64, base64url, base32, base58, hex, hex-upper, url, html)")),
+        }
+    }
+
+    /// 带填充与不带填充的 base64/base32 编码
+    fn padded(&self) -> Option<(Encoding, Encoding)> {
+        match self {
+            Codec::Base64 => Some((BASE64, BASE64_NOPAD)),
+            Codec::Base64Url => Some((BASE64URL, BASE64URL_NOPAD)),
+            Codec::Base32 => Some((BASE32, BASE32_NOPAD)),
+            _ => None,
+        }
+    }
+
+    fn encode(&self, data: &[u8], padding: bool) -> Result<String, String> {
+        if let Some((padded, unpadded)) = self.padded() {
+            return Ok(if padding { padded.encode(data) } else { unpadded.encode(data) });
+        }
+
+        match self {
+            Codec::Base58 => Ok(bs58::encode(data).into_string()),
+            Codec::Hex => Ok(HEXLOWER.encode(data)),
+            Codec::HexUpper => Ok(HEXUPPER.encode(data)),
+            Codec::Url => Ok(percent_encode(data, URL_ENCODE_SET).to_string()),
+            Codec::Html => Ok(html_escape::encode_safe(&utf8(data)?).to_string()),
+            _ => unreachable!(),
+        }
+    }
+
+    /// 解码: base64, base32 与十六进制忽略其中的空白字符, 填充可有可无; base58 与 URL 忽略首尾的空白字符
+    fn decode(&self, data: &[u8]) -> Result<Vec<u8>, String> {
+        let compact = || data.iter().filter(|byte| !byte.is_ascii_whitespace()).copied().collect::<Vec<u8>>();
+        let trimmed = || data.trim_ascii();
+
+        if let Some((_, unpadded)) = self.padded() {
+            let compact = compact();
+            let end = compact.iter().rposition(|byte| *byte != b'=').map_or(0, |pos| pos + 1);
+            return unpadded.decode(&compact[..end]).map_err(|err| format!("Invalid {} input ({err})", self.name()));
+        }
+
+        match self {
+            Codec::Base58 => bs58::decode(trimmed()).into_vec().map_err(|err| format!("Invalid base58 input ({err})")),
+            Codec::Hex | Codec::HexUpper => HEXLOWER_PERMISSIVE.decode(&compact()).map_err(|err| format!("Invalid hex input ({err})")),
+            Codec::Url => Ok(percent_decode(trimmed()).collect()),
+            Codec::Html => Ok(html_escape::decode_html_entities(&utf8(data)?).as_bytes().to_vec()),
+            _ => unreachable!(),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Codec::Base64 => "base64",
+            Codec::Base64Url => "base64url",
+            Codec::Base32 => "base32",
+            Codec::Base58 => "base58",
+            Codec::Hex | Codec::HexUpper => "hex",
+            Codec::Url => "url",
+            Codec::Html => "html",
+        }
+    }
+}
+
+fn utf8(data: &[u8]) -> Result<String, String> {
+    String::from_utf8(data.to_vec()).map_err(|_| "HTML entities only apply to UTF-8 text".to_string())
+}
+
+pub struct CodecImpl {}
+
+impl CodecImpl {
+    /// 读取输入: `filemode` 时 '-' 为标准输入, 其余路径为文件内容, 否则为字符串本身 (与 Commands::Hash 一致)
+    fn read_source(source: &str, filemode: bool) -> Result<Vec<u8>, String> {
+        if filemode && source == "-" {
+            let mut raw = vec![];
+            io::stdin().lock().read_to_end(&mut raw).map_err(|err| format!("{err}"))?;
+            Ok(raw)
+        } else if filemode {
+            fs::read(source).map_err(|err| format!("{source}: {err}"))
+        } else {
+            Ok(source.as_bytes().to_vec())
+        }
+    }
+
+    fn run(options: &CodecOptions) -> Result<String, String> {
+        let codec = Codec::parse(&options.encoding)?;
+        let data = CodecImpl::read_source(&options.source, options.filemode)?;
+        let result = match options.decode {
+            true => codec.decode(&data)?,
+            false => codec.encode(&data, !options.no_padding)?.into_bytes(),
+        };
+
+        match &options.output {
+            Some(output) => {
+                fs::write(output, &result).map_err(|err| format!("{output}: {err}"))?;
+                Ok(format!("Written to '{output}' ({} bytes)", result.len()))
+            }
+            // 解码结果可能是二进制数据, 无法直接输出
+            None => String::from_utf8(result).map_err(|_| "The decoded data is not valid UTF-8 text, use '--output' to write it to a file".to_string()),
+        }
+    }
+
+    /// 处理 Command::Encode 与 Command::Decode 子命令
+    pub fn handle(options: CodecOptions) {
+        let command = if options.decode { "Decode" } else { "Encode" };
+        println!("[Commands::{command}] source = '{}', filemode = '{}', encoding = '{}'", options.source, options.filemode, options.encoding);
+
+        match CodecImpl::run(&options) {
+            Ok(result) if result.contains('\n') => println!("Ok:\n{result}"),
+            Ok(result) => println!("Ok: {result}"),
+            Err(err) => {
+                println!("Error: {err}");
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod unit_test {
+    use super::*;
+
+    fn encode(encoding: &str, data: &str) -> String {
+        Codec::parse(encoding).unwrap().encode(data.as_bytes(), true).unwrap()
+    }
+
+    fn decode(encoding: &str, data: &str) -> String {
+        String::from_utf8(Codec::parse(encoding).unwrap().decode(data.as_bytes()).unwrap()).unwrap()
+    }
+
+    /// RFC 4648 中的测试向量
+    #[test]
+    fn rfc4648_test() {
+        for (encoding, encoded) in [("base64", "Zm9vYmFy"), ("base32", "MZXW6YTBOI======"), ("hex", "666f6f626172"), ("hex-upper", "666F6F626172")] {
+            assert_eq!(encode(encoding, "foobar"), encoded);
+            assert_eq!(decode(encoding, encoded), "foobar");
+        }
+        assert_eq!(encode("base64", "fo"), "Zm8=");
+        assert_eq!(Codec::Base64.encode(b"fo", false).unwrap(), "Zm8");
+        // 解码时填充与空白字符可有可无
+        assert_eq!(decode("base64", "Zm8"), "fo");
+        assert_eq!(decode("base32", "MZXW6\nYTBOI"), "foobar");
+        assert_eq!(decode("hex", "66 6F"), "fo");
+        assert_eq!(Codec::Base64.encode(&[0xfb, 0xff], true).unwrap(), "+/8=");
+        assert_eq!(Codec::Base64Url.encode(&[0xfb, 0xff], true).unwrap(), "-_8=");
+        assert!(Codec::Base64.decode(b"Zm9v!").is_err());
+    }
+
+    #[test]
+    fn text_codec_test() {
+        assert_eq!(encode("base58", "Hello World!"), "2NEpo7TZRRrLZSi2U");
+        assert_eq!(decode("base58", "2NEpo7TZRRrLZSi2U\n"), "Hello World!");
+        assert_eq!(encode("url", "a b&c/ü~"), "a%20b%26c%2F%C3%BC~");
+        assert_eq!(decode("url", "a%20b%26c%2F%C3%BC~"), "a b&c/ü~");
+        assert_eq!(encode("html", "<a href=\"x\">&</a>"), "&lt;a href=&quot;x&quot;&gt;&amp;&lt;&#x2F;a&gt;");
+        assert_eq!(decode("html", "&lt;p&gt;caf&eacute; &#169; &#x1F600;"), "<p>café © 😀");
+        assert_eq!(encode("Hex", "fo"), "666f");
+        assert_eq!(encode("HEX-UPPER", "fo"), "666F");
+        assert!(Codec::parse("HEX2").is_err());
+    }
+
+    #[test]
+    fn read_source_test() {
+        // 与 Commands::Hash 一致, 只有 filemode 下 '-' 才表示 stdin
+        assert_eq!(CodecImpl::read_source("-", false).unwrap(), b"-");
+        assert_eq!(CodecImpl::read_source("abc", false).unwrap(), b"abc");
+    }
+}
@@ -4,6 +4,7 @@ const MODE_DESC: &str = "Service mode, which determines how the server handles r
 const SINGLE_MODE: &str = "- single: All requests will get the entry file as a response.";
 const MIXED_MODE: &str = "- mixed: Requests with a suffix will be considered as required resources and try to load the target resource, and the rest of the requests will be directed to the entry file.";
 const DIRECT_MODE: &str = "- direct: First try to find the resource in the corresponding path under the root directory, if the resource exists and is a file type, return the resource, otherwise return the entry file.";
+const CODEC_ENCODING: &str = "Supported encodings:\n- base64, base64url (URL and filename safe)\n- base32\n- base58 (Bitcoin alphabet)\n- hex (lowercase), hex-upper (uppercase)\n- url (percent-encoding, only unreserved characters are kept)\n- html (HTML entities)\n";
 
 /// Cli instance
 #[derive(Parser, Debug)]
@@ -16,34 +17,256 @@ pub struct Cli {
 /// All supported command
 #[derive(Subcommand, Debug)]
 pub enum Commands {
+    /// Encrypt or decrypt a file with a password or a raw key using an authenticated cipher
+    #[command(about = "Encrypt or decrypt a file with a password or a raw key using an authenticated cipher")]
+    Crypt {
+        /// Source file to be encrypted or decrypted, or '-' to read from stdin
+        #[arg(help = "Source file to be encrypted or decrypted, or '-' to read from stdin")]
+        source: String,
+        /// Output file (default to the source with the '.enc' extension added when encrypting, or removed when decrypting)
+        #[arg(short, long, help = "Output file (default to the source with the '.enc' extension added when encrypting, or removed when decrypting)")]
+        output: Option<String>,
+        /// Decrypt the source instead of encrypting it. The algorithm and key derivation parameters are read from the encrypted file
+        #[arg(short, long, help = "Decrypt the source instead of encrypting it. The algorithm and key derivation parameters are read from the encrypted file", default_value = "false")]
+        decrypt: bool,
+        /// The encryption algorithm used (Supported values are: 'aes256gcm' (AES-256-GCM), 'chacha20poly1305' (ChaCha20-Poly1305), case insensitive)
+        #[arg(short, long, help = "The encryption algorithm used (Supported values are: 'aes256gcm' (AES-256-GCM), 'chacha20poly1305' (ChaCha20-Poly1305), case insensitive)", default_value = "aes256gcm")]
+        algorithm: String,
+        /// Password from which the key is derived with Argon2id
+        #[arg(short, long, help = "Password from which the key is derived with Argon2id")]
+        password: Option<String>,
+        /// Read the password from this file instead of '--password' (one trailing line break is ignored)
+        #[arg(long, help = "Read the password from this file instead of '--password' (one trailing line break is ignored)")]
+        password_file: Option<String>,
+        /// 32-byte key used directly instead of a password
+        #[arg(long, help = "32-byte key used directly instead of a password")]
+        key: Option<String>,
+        /// Read the key from this file instead of '--key'
+        #[arg(long, help = "Read the key from this file instead of '--key'")]
+        key_file: Option<String>,
+        /// How the key is encoded (Supported values are: 'utf8', 'hex', 'base64')
+        #[arg(long, help = "How the key is encoded (Supported values are: 'utf8', 'hex', 'base64')", default_value = "hex")]
+        key_encoding: String,
+        /// Overwrite the output if it already exists
+        #[arg(long, help = "Overwrite the output if it already exists", default_value = "false")]
+        force: bool,
+    },
+    /// Decode the specified source from a text encoding
+    #[command(about = "Decode the specified source from a text encoding")]
+    Decode {
+        /// Source text, or source file path (with 'filemode' true) to be decoded
+        #[arg(help = "Source text, or source file path (with 'filemode' true) to be decoded, or '-' (with 'filemode' true) to read from stdin")]
+        source: String,
+        /// Whether to treat source as a file path rather than a raw string (default to 'false')
+        #[arg(short, long, help = "Whether to treat source as a file path rather than a raw string (default to 'false')", default_value = "false")]
+        filemode: bool,
+        /// The encoding used. Whitespace and the '=' padding are optional for base64, base64url, base32 and hex
+        #[arg(short, long, help = CODEC_ENCODING, default_value = "base64")]
+        encoding: String,
+        /// Path to write the result to (required if the decoded data is not UTF-8 text). If omitted, the result will be printed
+        #[arg(short, long, help = "Path to write the result to (required if the decoded data is not UTF-8 text). If omitted, the result will be printed")]
+        output: Option<String>,
+    },
+    /// Find duplicate files in one or more directories
+    #[command(about = "Find duplicate files in one or more directories")]
+    Dedupe {
+        /// Directories to scan recursively. Files are grouped by size, then by a fast partial hash of the first 64 KiB, then by a full digest
+        #[arg(help = "Directories to scan recursively. Files are grouped by size, then by a fast partial hash of the first 64 KiB, then by a full digest", required = true)]
+        sources: Vec<String>,
+        /// The hash algorithm used for the full digest (Any algorithm supported by the 'hash' command)
+        #[arg(short, long, help = "The hash algorithm used for the full digest (Any algorithm supported by the 'hash' command)", default_value = "blake3")]
+        algorithm: String,
+        /// What to do with the duplicates, the first file (in path order) of each set is always kept (Supported values are: 'report', 'hardlink', 'delete')
+        #[arg(long, help = "What to do with the duplicates, the first file (in path order) of each set is always kept (Supported values are: 'report', 'hardlink', 'delete')", default_value = "report")]
+        action: String,
+        /// Only print what 'hardlink'/'delete' would do without touching any file
+        #[arg(long, help = "Only print what 'hardlink'/'delete' would do without touching any file", default_value = "false")]
+        dry_run: bool,
+        /// Ignore files smaller than this size in bytes
+        #[arg(long, help = "Ignore files smaller than this size in bytes", default_value = "1")]
+        min_size: u64,
+        /// Number of files hashed in parallel (default to the number of CPU cores)
+        #[arg(long, help = "Number of files hashed in parallel (default to the number of CPU cores)")]
+        jobs: Option<usize>,
+    },
+    /// Compare two directory trees (or a directory against a manifest) by content digest
+    #[command(about = "Compare two directory trees (or a directory against a manifest) by content digest")]
+    DiffDir {
+        /// The original directory, or a 'sha256sum'/'md5sum' style manifest (or BSD tagged format) of it
+        #[arg(help = "The original directory, or a 'sha256sum'/'md5sum' style manifest (or BSD tagged format) of it")]
+        left: String,
+        /// The directory (or manifest) to compare against
+        #[arg(help = "The directory (or manifest) to compare against")]
+        right: String,
+        /// The hash algorithm used (default to 'sha256'). With a manifest, the algorithm is implied by its tags or digest length (for a multi-algorithm manifest, this selects one of its algorithms)
+        #[arg(short, long, help = "The hash algorithm used (default to 'sha256'). With a manifest, the algorithm is implied by its tags or digest length (for a multi-algorithm manifest, this selects one of its algorithms)")]
+        algorithm: Option<String>,
+        /// Print the differences as a JSON object
+        #[arg(short, long, help = "Print the differences as a JSON object", default_value = "false")]
+        json: bool,
+        /// Number of files hashed in parallel (default to the number of CPU cores)
+        #[arg(long, help = "Number of files hashed in parallel (default to the number of CPU cores)")]
+        jobs: Option<usize>,
+    },
     /// Open the document in the default browser
     #[command(about = "Open the document in the default browser")]
     Doc,
-    /// Get the digest of the specified source
-    #[command(about = "Get the digest of the specified source")]
-    Hash {
-        /// Path to the target file
-        #[arg(help = "Source text or source file path (with 'filemode' true) to be evaluated ")]
+    /// Encode the specified source with a text encoding
+    #[command(about = "Encode the specified source with a text encoding")]
+    Encode {
+        /// Source text, or source file path (with 'filemode' true) to be encoded
+        #[arg(help = "Source text, or source file path (with 'filemode' true) to be encoded, or '-' (with 'filemode' true) to read from stdin")]
         source: String,
         /// Whether to treat source as a file path rather than a raw string (default to 'false')
         #[arg(short, long, help = "Whether to treat source as a file path rather than a raw string (default to 'false')", default_value = "false")]
         filemode: bool,
-        /// The hash algorithm used
-        #[arg(short, long, help = "Supported algorithms (case insensitive):\n- md5 (MD5)\n- ripemd (Ripemd128, Ripemd160, Ripemd256, Ripemd320)\n- sha1 (SHA1)\n- sha2 (SHA224, SHA256, SHA384, SHA512, SHA512_224, SHA512_256)\n- sha3 (SHA3_224, SHA3_256, SHA3_384, SHA3_512)\n", default_value = "MD5")]
+        /// The encoding used
+        #[arg(short, long, help = CODEC_ENCODING, default_value = "base64")]
+        encoding: String,
+        /// Path to write the result to. If omitted, the result will be printed
+        #[arg(short, long, help = "Path to write the result to. If omitted, the result will be printed")]
+        output: Option<String>,
+        /// Omit the '=' padding of base64, base64url and base32
+        #[arg(long, help = "Omit the '=' padding of base64, base64url and base32", default_value = "false")]
+        no_padding: bool,
+    },
+    /// Get the digest of the specified source
+    #[command(about = "Get the digest of the specified source")]
+    Hash {
+        /// Source text, or source file/directory path(s) (with 'filemode' true) to be evaluated
+        #[arg(help = "Source text or source file/directory path(s) (with 'filemode' true) to be evaluated, or '-' (with 'filemode' true) to read from stdin. Multiple paths (and directories) are hashed in parallel into a single manifest. With '--check', the directory the listed paths are relative to (default to the directory of the manifest)", required_unless_present = "check")]
+        sources: Vec<String>,
+        /// Whether to treat source as a file path rather than a raw string (default to 'false'). If the path points to a directory, every file in it will be hashed recursively and a 'sha256sum'/'md5sum' compatible manifest will be generated
+        #[arg(short, long, help = "Whether to treat source as a file path rather than a raw string (default to 'false'). If the path points to a directory, every file in it will be hashed recursively and a 'sha256sum'/'md5sum' compatible manifest will be generated", default_value = "false")]
+        filemode: bool,
+        /// The hash algorithm(s) used. Multiple algorithms can be separated by commas (e.g. 'md5,sha1,sha256'), or use 'all' for every supported algorithm; the source is only read once
+        #[arg(short, long, help = "Supported algorithms (case insensitive):\n- md5 (MD5)\n- ripemd (Ripemd128, Ripemd160, Ripemd256, Ripemd320)\n- sha1 (SHA1)\n- sha2 (SHA224, SHA256, SHA384, SHA512, SHA512_224, SHA512_256)\n- sha3 (SHA3_224, SHA3_256, SHA3_384, SHA3_512, SHAKE128, SHAKE256, cSHAKE128, cSHAKE256)\n- keccak, pre-standard SHA-3 as used by Ethereum (Keccak224, Keccak256, Keccak384, Keccak512)\n- blake (BLAKE2b, BLAKE2s, BLAKE3)\n- checksum, non-cryptographic (CRC32, CRC32C, Adler32, XXH32, XXH64, XXH3, XXH3_128, Murmur3_32, Murmur3_128)\nMultiple algorithms can be separated by commas (e.g. 'md5,sha1,sha256'), or use 'all' for every supported algorithm. The source is only read once\n", default_value = "MD5")]
         algorithm: String,
+        /// Path to write the manifest to when hashing a directory. If omitted, the manifest will be printed
+        #[arg(short, long, help = "Path to write the manifest to when hashing a directory. If omitted, the manifest will be printed")]
+        output: Option<String>,
+        /// Path to a 'sha256sum'/'md5sum' style manifest (or BSD 'SHA256 (file) = ...' tagged format) to verify. Each listed file is recomputed with the algorithm implied by the tag or the digest length, and reported as OK/FAILED/MISSING. Exits with a non-zero code if any file fails
+        #[arg(short, long, help = "Path to a 'sha256sum'/'md5sum' style manifest (or BSD 'SHA256 (file) = ...' tagged format) to verify. Each listed file is recomputed with the algorithm implied by the tag or the digest length, and reported as OK/FAILED/MISSING. Exits with a non-zero code if any file fails", value_name = "MANIFEST")]
+        check: Option<String>,
+        /// Print the result as a JSON object (keyed by algorithm, and by relative path when hashing a directory)
+        #[arg(short, long, help = "Print the result as a JSON object (keyed by algorithm, and by relative path when hashing a directory)", default_value = "false")]
+        json: bool,
+        /// 32-byte key (64 hex digits) for the BLAKE3 keyed hash mode
+        #[arg(long, help = "32-byte key (64 hex digits) for the BLAKE3 keyed hash mode")]
+        blake3_key: Option<String>,
+        /// Context string for the BLAKE3 key derivation mode
+        #[arg(long, help = "Context string for the BLAKE3 key derivation mode")]
+        blake3_context: Option<String>,
+        /// Compute the HMAC of the source with this key instead of a plain digest (only for md5, ripemd, sha1, sha2, sha3 and blake2)
+        #[arg(long, help = "Compute the HMAC of the source with this key instead of a plain digest (only for md5, ripemd, sha1, sha2, sha3 and blake2)")]
+        hmac_key: Option<String>,
+        /// Read the HMAC key from this file instead of '--hmac-key' (one trailing line break is ignored)
+        #[arg(long, help = "Read the HMAC key from this file instead of '--hmac-key' (one trailing line break is ignored)")]
+        hmac_key_file: Option<String>,
+        /// How the HMAC key is encoded (Supported values are: 'utf8', 'hex', 'base64')
+        #[arg(long, help = "How the HMAC key is encoded (Supported values are: 'utf8', 'hex', 'base64')", default_value = "utf8")]
+        hmac_key_encoding: String,
+        /// Compare the digest with this value instead of printing it, and exit with a non-zero code on mismatch. An algorithm prefix such as 'sha256=' (as in webhook signature headers) is accepted
+        #[arg(long, help = "Compare the digest with this value instead of printing it, and exit with a non-zero code on mismatch. An algorithm prefix such as 'sha256=' (as in webhook signature headers) is accepted")]
+        expect: Option<String>,
+        /// Output encoding of the digest (Supported values are: 'hex' (lowercase, default), 'hex-upper' (uppercase), 'base64', 'base64url' (without padding), 'base32', 'sri' (Subresource Integrity, e.g. 'sha384-...', only for sha256/sha384/sha512), case insensitive. Manifests without '--json' must be hex)
+        #[arg(short, long, help = "Output encoding of the digest (Supported values are: 'hex' (lowercase), 'hex-upper' (uppercase), 'base64', 'base64url' (without padding), 'base32', 'sri' (Subresource Integrity, e.g. 'sha384-...', only for sha256/sha384/sha512), case insensitive. Manifests without '--json' must be hex)", default_value = "hex")]
+        encoding: String,
+        /// How the source text (or stdin) is decoded into bytes before hashing (Supported values are: 'utf8' (raw bytes), 'hex', 'base64', 'utf16le')
+        #[arg(long, help = "How the source text (or stdin) is decoded into bytes before hashing (Supported values are: 'utf8' (raw bytes), 'hex', 'base64', 'utf16le')", default_value = "utf8")]
+        input_encoding: String,
+        /// Number of files hashed in parallel when hashing directories or multiple paths (default to the number of CPU cores)
+        #[arg(long, help = "Number of files hashed in parallel when hashing directories or multiple paths (default to the number of CPU cores)")]
+        jobs: Option<usize>,
+        /// Output length in bytes of the extendable-output functions, at most 1048576 (1 MiB) (default to 32 for shake128/cshake128, 64 for shake256/cshake256)
+        #[arg(long, help = "Output length in bytes of the extendable-output functions, at most 1048576 (1 MiB) (default to 32 for shake128/cshake128, 64 for shake256/cshake256)")]
+        output_length: Option<usize>,
+        /// Customization string of cSHAKE (cshake128, cshake256)
+        #[arg(long, help = "Customization string of cSHAKE (cshake128, cshake256)")]
+        customization: Option<String>,
+        /// Compute Git object IDs instead of plain digests: blob IDs for files and strings, tree IDs (as 'git write-tree' would produce) for directories. The object format is 'sha1' (default) or 'sha256'
+        #[arg(long, help = "Compute Git object IDs instead of plain digests: blob IDs for files and strings, tree IDs (as 'git write-tree' would produce) for directories. The object format is 'sha1' (default) or 'sha256'", value_name = "FORMAT", num_args = 0..=1, default_missing_value = "sha1")]
+        git: Option<String>,
+        /// Treat the source as an archive ('*.7z', '*.tar', '*.tgz', '*.tar.gz', '*.zip', '*.gz', case insensitive) and hash every file inside it without extracting to disk. Combined with '--check', the members listed in the manifest are verified instead
+        #[arg(long, help = "Treat the source as an archive ('*.7z', '*.tar', '*.tgz', '*.tar.gz', '*.zip', '*.gz', case insensitive) and hash every file inside it without extracting to disk. Combined with '--check', the members listed in the manifest are verified instead", default_value = "false")]
+        archive: bool,
+    },
+    /// Convert text files between character encodings (UTF-8, UTF-16LE/BE, GBK/GB18030, Shift_JIS, EUC-KR, Big5, Windows-125x, ...)
+    #[command(about = "Convert text files between character encodings (UTF-8, UTF-16LE/BE, GBK/GB18030, Shift_JIS, EUC-KR, Big5, Windows-125x, ...)")]
+    Iconv {
+        /// Files or directories to convert (directories are walked recursively). Files are converted in place unless '--output' is given
+        #[arg(help = "Files or directories to convert (directories are walked recursively). Files are converted in place unless '--output' is given", required = true)]
+        sources: Vec<String>,
+        /// Source encoding. If omitted, it is detected from the BOM, or guessed from the content (valid UTF-8 first, then the most likely legacy encoding). A BOM in the source always takes precedence and is stripped
+        #[arg(short, long, help = "Source encoding. If omitted, it is detected from the BOM, or guessed from the content (valid UTF-8 first, then the most likely legacy encoding). A BOM in the source always takes precedence and is stripped")]
+        from: Option<String>,
+        /// Target encoding (Any WHATWG encoding label, e.g. 'utf-8', 'utf-16le', 'utf-16be', 'gbk', 'gb18030', 'shift_jis', 'euc-jp', 'euc-kr', 'big5', 'windows-1252', case insensitive)
+        #[arg(short, long, help = "Target encoding (Any WHATWG encoding label, e.g. 'utf-8', 'utf-16le', 'utf-16be', 'gbk', 'gb18030', 'shift_jis', 'euc-jp', 'euc-kr', 'big5', 'windows-1252', case insensitive)", default_value = "utf-8")]
+        to: String,
+        /// Output file, or output directory when the source is a directory (the relative paths are kept). Only allowed with a single source
+        #[arg(short, long, help = "Output file, or output directory when the source is a directory (the relative paths are kept). Only allowed with a single source")]
+        output: Option<String>,
+        /// Add a BOM to the output (only for UTF-8, UTF-16LE and UTF-16BE). Without it, the output has no BOM
+        #[arg(long, help = "Add a BOM to the output (only for UTF-8, UTF-16LE and UTF-16BE). Without it, the output has no BOM", default_value = "false")]
+        bom: bool,
+        /// Only convert files with these extensions in directories, separated by commas (e.g. 'c,h,cpp,txt')
+        #[arg(short, long, help = "Only convert files with these extensions in directories, separated by commas (e.g. 'c,h,cpp,txt')")]
+        extensions: Option<String>,
+        /// Only print the detected encodings without writing any file
+        #[arg(long, help = "Only print the detected encodings without writing any file", default_value = "false")]
+        dry_run: bool,
     },
-    /// Convert the specified image to the specified format and/or size (simple show the metadata of the image if both format and size are omitted)
-    #[command(about = "Convert the specified image to the specified format and/or size  (simple show the metadata of the image if both format and size are omitted)")]
+    /// Convert the specified image to the specified format and/or size (simple show the metadata of the image if the format, size and encoder options are all omitted)
+    #[command(about = "Convert the specified image to the specified format and/or size  (simple show the metadata of the image if the format, size and encoder options are all omitted)")]
     Image {
-        /// Path to the source image
-        #[arg(help = "Path to the source image")]
+        /// Path to the source image (The format is detected from the file content, the extension is only needed for TGA)
+        #[arg(help = "Path to the source image (The format is detected from the file content, the extension is only needed for TGA)")]
         source: String,
-        /// Target image format. If omitted, the format of the source image will be used. (Supported values are: 'bmp', 'gif', 'ico', 'jpg'('jpeg'), 'png', 'tiff', case insensitive)
-        #[arg(short, long, help = "Target image format (Supported values are: 'bmp', 'gif', 'ico', 'jpg'('jpeg'), 'png', 'tiff', case insensitive)")]
+        /// Target image format. If omitted, the format of the source image will be used ('png' if the source format can only be read, e.g. 'dds'). (Supported values are: 'avif', 'bmp', 'exr', 'ff' (Farbfeld), 'gif', 'hdr', 'ico', 'jpg'('jpeg'), 'pam', 'pbm', 'pgm', 'png', 'ppm'('pnm'), 'qoi', 'tga', 'tiff', 'webp', case insensitive)
+        #[arg(short, long, help = "Target image format (Supported values are: 'avif', 'bmp', 'exr', 'ff' (Farbfeld), 'gif', 'hdr', 'ico', 'jpg'('jpeg'), 'pam', 'pbm', 'pgm', 'png', 'ppm'('pnm'), 'qoi', 'tga', 'tiff', 'webp', case insensitive)")]
         format: Option<String>,
         /// Target image size. This should be in the format of '(width)x(height)'. If only one of the width and height is specified, the other will be scaled proportionally. If both are omitted, the original size will be used. (e.g. '100x200' or 'x200' or '100x'.)
         #[arg(short, long, help = "Target image size. This should be in the format of '(width)x(height)'. If only one of the width and height is specified, the other will be scaled proportionally. If both are omitted, the original size will be used. (e.g. '100x200' or 'x200' or '100x'.)")]
         size: Option<String>,
+        /// Path of the output image (default to '<stem>[@<width>x<height>].<format>' next to the source). The source image is never overwritten
+        #[arg(short, long, help = "Path of the output image (default to '<stem>[@<width>x<height>].<format>' next to the source). The source image is never overwritten")]
+        output: Option<String>,
+        /// Resampling filter used when resizing (Supported values are: 'nearest', 'triangle', 'catmull-rom', 'gaussian', 'lanczos3', case insensitive)
+        #[arg(long, help = "Resampling filter used when resizing (Supported values are: 'nearest', 'triangle', 'catmull-rom', 'gaussian', 'lanczos3', case insensitive)", default_value = "lanczos3")]
+        filter: String,
+        /// How the image is fitted when both width and height are specified, the same as sharp (Supported values are: 'contain' (letterbox with transparent padding), 'cover' (crop to the exact size), 'fill' (stretch, ignoring the aspect ratio), 'inside' (within the size), 'outside' (covering the size), case insensitive)
+        #[arg(long, help = "How the image is fitted when both width and height are specified, the same as sharp (Supported values are: 'contain' (letterbox with transparent padding), 'cover' (crop to the exact size), 'fill' (stretch, ignoring the aspect ratio), 'inside' (within the size), 'outside' (covering the size), case insensitive)", default_value = "inside")]
+        fit: String,
+        /// Which part of the image is kept with 'cover', or where it is placed with 'contain' (Supported values are: 'center', 'north', 'northeast', 'east', 'southeast', 'south', 'southwest', 'west', 'northwest', case insensitive)
+        #[arg(long, help = "Which part of the image is kept with 'cover', or where it is placed with 'contain' (Supported values are: 'center', 'north', 'northeast', 'east', 'southeast', 'south', 'southwest', 'west', 'northwest', case insensitive)", default_value = "center")]
+        gravity: String,
+        /// Quality (1-100) of the lossy output formats (jpg, webp, avif) (default to 80)
+        #[arg(short, long, help = "Quality (1-100) of the lossy output formats (jpg, webp, avif) (default to 80)")]
+        quality: Option<u8>,
+        /// Write a progressive JPEG, which is usually smaller and renders gradually in browsers
+        #[arg(long, help = "Write a progressive JPEG, which is usually smaller and renders gradually in browsers", default_value = "false")]
+        progressive: bool,
+        /// Write a lossless WebP ('--quality' is ignored)
+        #[arg(long, help = "Write a lossless WebP ('--quality' is ignored)", default_value = "false")]
+        lossless: bool,
+        /// AVIF encoding speed, from 1 (slowest, smallest file) to 10 (fastest) (default to 4)
+        #[arg(long, help = "AVIF encoding speed, from 1 (slowest, smallest file) to 10 (fastest) (default to 4)")]
+        speed: Option<u8>,
+        /// Compression level of the PNG output (Supported values are: 'fast', 'default', 'best', case insensitive) (default to 'fast')
+        #[arg(long, help = "Compression level of the PNG output (Supported values are: 'fast', 'default', 'best', case insensitive) (default to 'fast')")]
+        png_compression: Option<String>,
+        /// Filter of the PNG output (Supported values are: 'none', 'sub', 'up', 'avg', 'paeth', 'adaptive', case insensitive) (default to 'adaptive')
+        #[arg(long, help = "Filter of the PNG output (Supported values are: 'none', 'sub', 'up', 'avg', 'paeth', 'adaptive', case insensitive) (default to 'adaptive')")]
+        png_filter: Option<String>,
+        /// Compression of the TIFF output (Supported values are: 'none', 'lzw', 'deflate', 'packbits', case insensitive) (default to 'none')
+        #[arg(long, help = "Compression of the TIFF output (Supported values are: 'none', 'lzw', 'deflate', 'packbits', case insensitive) (default to 'none')")]
+        tiff_compression: Option<String>,
+        /// How HDR images ('hdr', 'exr') are mapped to 8/16-bit target formats (Supported values are: 'reinhard', 'aces', 'clamp', case insensitive)
+        #[arg(long, help = "How HDR images ('hdr', 'exr') are mapped to 8/16-bit target formats (Supported values are: 'reinhard', 'aces', 'clamp', case insensitive)", default_value = "reinhard")]
+        tone_map: String,
+        /// Exposure adjustment in stops applied to HDR images before tone mapping (e.g. '1' doubles the brightness, '-1' halves it)
+        #[arg(long, help = "Exposure adjustment in stops applied to HDR images before tone mapping (e.g. '1' doubles the brightness, '-1' halves it)", default_value = "0", allow_negative_numbers = true)]
+        exposure: f32,
     },
     /// Pack any number of files or directories (possibly both) into an archive or compressed package of the specified format. (Supported values are: '\*.7z', '\*.tar', '\*.tgz', '\*.tar.gz', '\*.zip', case insensitive)
     #[command(about = "Pack any number of files or directories (possibly both) into an archive or compressed package of the specified format. (Supported values are: '*.7z', '*.tar', '*.tgz', '*.tar.gz', '*.zip', case insensitive)")]
@@ -55,6 +278,37 @@ pub enum Commands {
         #[arg(help = "The path to the directory(s) or file(s) to be packaged")]
         sources: Vec<String>,
     },
+    /// Generate or verify a password hash (Argon2, bcrypt, scrypt, PBKDF2)
+    #[command(about = "Generate or verify a password hash (Argon2, bcrypt, scrypt, PBKDF2)")]
+    Password {
+        /// The password to be hashed or verified, or '-' to read it from stdin (a single line)
+        #[arg(help = "The password to be hashed or verified, or '-' to read it from stdin (a single line)")]
+        password: String,
+        /// Password hashing algorithm (Supported values are: 'argon2id', 'argon2i', 'argon2d', 'bcrypt', 'scrypt', 'pbkdf2' (PBKDF2-HMAC-SHA256), 'pbkdf2-sha512', case insensitive)
+        #[arg(short, long, help = "Password hashing algorithm (Supported values are: 'argon2id', 'argon2i', 'argon2d', 'bcrypt', 'scrypt', 'pbkdf2' (PBKDF2-HMAC-SHA256), 'pbkdf2-sha512', case insensitive)", default_value = "argon2id")]
+        algorithm: String,
+        /// Verify the password against this hash (PHC string such as '$argon2id$...', or bcrypt '$2b$...') instead of generating one. The algorithm and parameters are taken from the hash. Exits with a non-zero code on mismatch
+        #[arg(short, long, help = "Verify the password against this hash (PHC string such as '$argon2id$...', or bcrypt '$2b$...') instead of generating one. The algorithm and parameters are taken from the hash. Exits with a non-zero code on mismatch", value_name = "HASH")]
+        verify: Option<String>,
+        /// Salt in hex (16 bytes for bcrypt). A random salt is generated if omitted
+        #[arg(short, long, help = "Salt in hex (16 bytes for bcrypt). A random salt is generated if omitted")]
+        salt: Option<String>,
+        /// Number of iterations: PBKDF2 rounds (default to 600000) or Argon2 time cost (default to 2)
+        #[arg(long, help = "Number of iterations: PBKDF2 rounds (default to 600000) or Argon2 time cost (default to 2)")]
+        iterations: Option<u32>,
+        /// Argon2 memory cost in KiB (default to 19456)
+        #[arg(long, help = "Argon2 memory cost in KiB (default to 19456)")]
+        memory: Option<u32>,
+        /// Degree of parallelism: Argon2 lanes or scrypt 'p' (default to 1)
+        #[arg(long, help = "Degree of parallelism: Argon2 lanes or scrypt 'p' (default to 1)")]
+        parallelism: Option<u32>,
+        /// Work factor: bcrypt cost (default to 12) or scrypt log2(N) (default to 17)
+        #[arg(long, help = "Work factor: bcrypt cost (default to 12) or scrypt log2(N) (default to 17)")]
+        cost: Option<u32>,
+        /// Length of the derived hash in bytes for Argon2, scrypt and PBKDF2 (default to 32)
+        #[arg(long, help = "Length of the derived hash in bytes for Argon2, scrypt and PBKDF2 (default to 32)")]
+        length: Option<usize>,
+    },
     /// Start a static resource server in the specified directory
     #[command(about = "Start a static resource server in the specified directory")]
     Serve {
@@ -71,6 +325,22 @@ pub enum Commands {
         #[arg(short, long, help = format!("{MODE_DESC}\n{SINGLE_MODE}\n{MIXED_MODE}\n{DIRECT_MODE}\n"), default_value = "mixed")]
         mode: String,
     },
+    /// Compute Subresource Integrity values of the local scripts and stylesheets referenced by an HTML entry
+    #[command(about = "Compute Subresource Integrity values of the local scripts and stylesheets referenced by an HTML entry")]
+    Sri {
+        /// Root directory, absolute resource URLs (starting with '/') are resolved against it, default to '.'
+        #[arg(short, long, help = "Root directory, absolute resource URLs (starting with '/') are resolved against it\n", default_value = ".")]
+        root: String,
+        /// Entry file (relative to the root), default to 'index.html'
+        #[arg(short, long, help = "Entry file (relative to the root)\n", default_value = "index.html")]
+        entry: String,
+        /// The hash algorithm used (Supported values are: 'sha256', 'sha384', 'sha512')
+        #[arg(short, long, help = "The hash algorithm used (Supported values are: 'sha256', 'sha384', 'sha512')\n", default_value = "sha384")]
+        algorithm: String,
+        /// Write the 'integrity' attributes back to the entry file instead of only printing a report. Without it, exits with a non-zero code if any attribute is missing or outdated
+        #[arg(short, long, help = "Write the 'integrity' attributes back to the entry file instead of only printing a report. Without it, exits with a non-zero code if any attribute is missing or outdated", default_value = "false")]
+        write: bool,
+    },
     /// Unpack the specified archive or compressed package into the specified directory. (Supported values are: '\*.7z', '\*.tar', '\*.tgz', '\*.tar.gz', '\*.zip', case insensitive)
     #[command(about = "Unpack the specified archive or compressed package into the specified directory. (Supported values are: '*.7z', '*.tar', '*.tgz'/'*.tar.gz', '*.zip', case insensitive)")]
     Unpack {
@@ -0,0 +1,590 @@
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use chacha20poly1305::ChaCha20Poly1305;
+use crate::hash::{strip_line_ending, HashImpl};
+
+/// 文件头的魔数
+const MAGIC: &[u8; 4] = b"RSTC";
+/// 容器格式的版本
+const VERSION: u8 = 1;
+/// 每一块明文的字节数
+const CHUNK_SIZE: u32 = 64 * 1024;
+/// 解密时允许的最大块大小 (避免损坏的文件头导致分配过多内存)
+const MAX_CHUNK_SIZE: u32 = 16 * 1024 * 1024;
+/// 认证标签的字节数 (AES-GCM 与 ChaCha20-Poly1305 相同)
+const TAG_SIZE: usize = 16;
+/// 密钥的字节数
+const KEY_SIZE: usize = 32;
+const SALT_SIZE: usize = 16;
+/// nonce 前缀的字节数 (12 字节 nonce = 7 字节前缀 + 4 字节块序号 + 1 字节结束标志)
+const PREFIX_SIZE: usize = 7;
+/// Argon2id 的默认参数 (与 `Commands::Password` 一致: 19 MiB, 2 轮, 1 线程)
+const ARGON2_MEMORY: u32 = 19 * 1024;
+const ARGON2_ITERATIONS: u32 = 2;
+const ARGON2_PARALLELISM: u32 = 1;
+/// 解密时允许的 Argon2id 参数上限: 文件头未经认证, 过大的参数会在派生密钥时耗尽内存或 CPU
+const MAX_ARGON2_MEMORY: u32 = 4 * 1024 * 1024;
+const MAX_ARGON2_ITERATIONS: u32 = 64;
+const MAX_ARGON2_PARALLELISM: u32 = 64;
+/// 未指定输出路径时加密结果的扩展名
+const EXTENSION: &str = ".enc";
+/// 输出先写入同一目录下的临时文件 (输出路径 + 该后缀), 成功后再重命名
+const TEMP_SUFFIX: &str = ".rstool-crypt";
+
+/// Arguments of `Commands::Crypt`
+pub struct CryptOptions {
+    pub source: String,
+    pub output: Option<String>,
+    pub decrypt: bool,
+    pub algorithm: String,
+    pub password: Option<String>,
+    pub password_file: Option<String>,
+    pub key: Option<String>,
+    pub key_file: Option<String>,
+    pub key_encoding: String,
+    pub force: bool,
+}
+
+/// 支持的加密算法 (大小写不敏感)
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Algorithm {
+    /// AES-256-GCM
+    Aes256Gcm,
+    /// ChaCha20-Poly1305 (RFC 8439)
+    ChaCha20Poly1305,
+}
+
+impl Algorithm {
+    fn parse(algorithm: &str) -> Result<Algorithm, String> {
+        match algorithm.to_lowercase().as_str() {
+            "aes256gcm" => Ok(Algorithm::Aes256Gcm),
+            "chacha20poly1305" => Ok(Algorithm::ChaCha20Poly1305),
+            _ => Err(format!("Invalid algorithm '{algorithm}' (Expect one of: aes256gcm, chacha20poly1305)")),
+        }
+    }
+
+    /// 文件头中的算法标识
+    fn id(&self) -> u8 {
+        match self {
+            Algorithm::Aes256Gcm => 1,
+            Algorithm::ChaCha20Poly1305 => 2,
+        }
+    }
+
+    fn from_id(id: u8) -> Result<Algorithm, String> {
+        match id {
+            1 => Ok(Algorithm::Aes256Gcm),
+            2 => Ok(Algorithm::ChaCha20Poly1305),
+            _ => Err(format!("Unsupported algorithm id {id}")),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Algorithm::Aes256Gcm => "aes256gcm",
+            Algorithm::ChaCha20Poly1305 => "chacha20poly1305",
+        }
+    }
+}
+
+/// 用于派生密钥的口令, 或直接使用的 32 字节密钥
+enum Secret {
+    Password(Vec<u8>),
+    Key(Vec<u8>),
+}
+
+/// Argon2id 的参数, 随文件头一起保存
+#[derive(Debug, Clone, PartialEq)]
+struct Kdf {
+    /// 内存大小 (KiB)
+    memory: u32,
+    iterations: u32,
+    parallelism: u32,
+    salt: [u8; SALT_SIZE],
+}
+
+impl Kdf {
+    fn derive_key(&self, password: &[u8]) -> Result<[u8; KEY_SIZE], String> {
+        let params = argon2::Params::new(self.memory, self.iterations, self.parallelism, Some(KEY_SIZE))
+            .map_err(|err| format!("Invalid Argon2 parameters ({err})"))?;
+        let mut key = [0u8; KEY_SIZE];
+        argon2::Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params)
+            .hash_password_into(password, &self.salt, &mut key)
+            .map_err(|err| format!("{err}"))?;
+        Ok(key)
+    }
+}
+
+/// 文件头, 整个文件头同时作为每一块的附加认证数据 (AAD)
+#[derive(Debug, Clone, PartialEq)]
+struct Header {
+    algorithm: Algorithm,
+    /// 使用口令时的密钥派生参数, 直接使用密钥时为 `None`
+    kdf: Option<Kdf>,
+    chunk_size: u32,
+    nonce_prefix: [u8; PREFIX_SIZE],
+}
+
+impl Header {
+    /// 生成新的文件头 (随机的盐与 nonce 前缀)
+    fn new(algorithm: Algorithm, secret: &Secret) -> Header {
+        let kdf = match secret {
+            Secret::Password(_) => {
+                let mut salt = [0u8; SALT_SIZE];
+                OsRng.fill_bytes(&mut salt);
+                Some(Kdf { memory: ARGON2_MEMORY, iterations: ARGON2_ITERATIONS, parallelism: ARGON2_PARALLELISM, salt })
+            }
+            Secret::Key(_) => None,
+        };
+        let mut nonce_prefix = [0u8; PREFIX_SIZE];
+        OsRng.fill_bytes(&mut nonce_prefix);
+
+        Header { algorithm, kdf, chunk_size: CHUNK_SIZE, nonce_prefix }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+        bytes.push(self.algorithm.id());
+        match &self.kdf {
+            Some(kdf) => {
+                bytes.push(1);
+                bytes.extend_from_slice(&kdf.memory.to_be_bytes());
+                bytes.extend_from_slice(&kdf.iterations.to_be_bytes());
+                bytes.extend_from_slice(&kdf.parallelism.to_be_bytes());
+                bytes.extend_from_slice(&kdf.salt);
+            }
+            None => bytes.push(0),
+        }
+        bytes.extend_from_slice(&self.chunk_size.to_be_bytes());
+        bytes.extend_from_slice(&self.nonce_prefix);
+        bytes
+    }
+
+    /// 读取并解析文件头, 返回文件头及其原始字节 (用作 AAD)
+    fn read(reader: &mut impl Read) -> Result<(Header, Vec<u8>), String> {
+        let mut raw = vec![];
+        let mut take = |len: usize| -> Result<Vec<u8>, String> {
+            let bytes = read_full(reader, len)?;
+            if bytes.len() < len {
+                return Err("Not an encrypted file (the header is truncated)".to_string());
+            }
+            raw.extend_from_slice(&bytes);
+            Ok(bytes)
+        };
+        let u32_at = |bytes: &[u8], offset: usize| u32::from_be_bytes([bytes[offset], bytes[offset + 1], bytes[offset + 2], bytes[offset + 3]]);
+
+        let start = take(7)?;
+        if &start[..4] != MAGIC {
+            return Err("Not an encrypted file (bad magic number)".to_string());
+        }
+        if start[4] != VERSION {
+            return Err(format!("Unsupported format version {}", start[4]));
+        }
+        let algorithm = Algorithm::from_id(start[5])?;
+        let kdf = match start[6] {
+            0 => None,
+            1 => {
+                let params = take(12 + SALT_SIZE)?;
+                let mut salt = [0u8; SALT_SIZE];
+                salt.copy_from_slice(&params[12..]);
+                let kdf = Kdf { memory: u32_at(&params, 0), iterations: u32_at(&params, 4), parallelism: u32_at(&params, 8), salt };
+                if kdf.memory > MAX_ARGON2_MEMORY || kdf.iterations > MAX_ARGON2_ITERATIONS || kdf.parallelism > MAX_ARGON2_PARALLELISM {
+                    return Err(format!(
+                        "Argon2 parameters exceed the limits (memory = {} KiB, iterations = {}, lanes = {}; at most {MAX_ARGON2_MEMORY} KiB, {MAX_ARGON2_ITERATIONS}, {MAX_ARGON2_PARALLELISM})",
+                        kdf.memory, kdf.iterations, kdf.parallelism
+                    ));
+                }
+                Some(kdf)
+            }
+            id => return Err(format!("Unsupported key derivation id {id}")),
+        };
+        let rest = take(4 + PREFIX_SIZE)?;
+        let chunk_size = u32_at(&rest, 0);
+        if chunk_size == 0 || chunk_size > MAX_CHUNK_SIZE {
+            return Err(format!("Invalid chunk size {chunk_size}"));
+        }
+        let mut nonce_prefix = [0u8; PREFIX_SIZE];
+        nonce_prefix.copy_from_slice(&rest[4..]);
+
+        Ok((Header { algorithm, kdf, chunk_size, nonce_prefix }, raw))
+    }
+
+    /// 根据文件头确定实际使用的密钥
+    fn key(&self, secret: &Secret) -> Result<[u8; KEY_SIZE], String> {
+        match (&self.kdf, secret) {
+            (Some(kdf), Secret::Password(password)) => kdf.derive_key(password),
+            (None, Secret::Key(key)) => <[u8; KEY_SIZE]>::try_from(key.as_slice())
+                .map_err(|_| format!("The key must be {KEY_SIZE} bytes, got {} bytes", key.len())),
+            (Some(_), Secret::Key(_)) => Err("The file is encrypted with a password, not a raw key".to_string()),
+            (None, Secret::Password(_)) => Err("The file is encrypted with a raw key, not a password".to_string()),
+        }
+    }
+}
+
+/// 已初始化密钥的加密算法
+enum Cipher {
+    Aes256Gcm(Box<Aes256Gcm>),
+    ChaCha20Poly1305(ChaCha20Poly1305),
+}
+
+impl Cipher {
+    fn new(algorithm: Algorithm, key: &[u8; KEY_SIZE]) -> Cipher {
+        match algorithm {
+            Algorithm::Aes256Gcm => Cipher::Aes256Gcm(Box::new(Aes256Gcm::new(key.into()))),
+            Algorithm::ChaCha20Poly1305 => Cipher::ChaCha20Poly1305(ChaCha20Poly1305::new(key.into())),
+        }
+    }
+
+    fn seal(&self, nonce: &[u8; 12], msg: &[u8], aad: &[u8]) -> Result<Vec<u8>, String> {
+        let payload = Payload { msg, aad };
+        match self {
+            Cipher::Aes256Gcm(cipher) => cipher.encrypt(Nonce::from_slice(nonce), payload),
+            Cipher::ChaCha20Poly1305(cipher) => cipher.encrypt(Nonce::from_slice(nonce), payload),
+        }.map_err(|_| "Encryption failed".to_string())
+    }
+
+    fn open(&self, nonce: &[u8; 12], msg: &[u8], aad: &[u8]) -> Result<Vec<u8>, String> {
+        let payload = Payload { msg, aad };
+        match self {
+            Cipher::Aes256Gcm(cipher) => cipher.decrypt(Nonce::from_slice(nonce), payload),
+            Cipher::ChaCha20Poly1305(cipher) => cipher.decrypt(Nonce::from_slice(nonce), payload),
+        }.map_err(|_| "Decryption failed (wrong password or key, or the data has been tampered with)".to_string())
+    }
+}
+
+/// STREAM 构造中第 `counter` 块的 nonce: 前缀 (7 字节) + 块序号 (u32, 大端) + 是否为最后一块 (1 字节)
+fn chunk_nonce(prefix: &[u8; PREFIX_SIZE], counter: u32, last: bool) -> [u8; 12] {
+    let mut nonce = [0u8; 12];
+    nonce[..PREFIX_SIZE].copy_from_slice(prefix);
+    nonce[PREFIX_SIZE..11].copy_from_slice(&counter.to_be_bytes());
+    nonce[11] = last as u8;
+    nonce
+}
+
+/// 读取 `len` 字节, 只有到达末尾时才会少于 `len` 字节
+fn read_full(reader: &mut impl Read, len: usize) -> Result<Vec<u8>, String> {
+    let mut buffer = vec![];
+    reader.take(len as u64).read_to_end(&mut buffer).map_err(|err| format!("{err}"))?;
+    Ok(buffer)
+}
+
+/// 按块处理 `reader` 中的数据, 通过预读下一块确定最后一块, 每一块依次交给 `f(块, 块序号, 是否为最后一块)`
+fn for_each_chunk<F>(reader: &mut impl Read, size: usize, mut f: F) -> Result<(), String>
+where
+    F: FnMut(&[u8], u32, bool) -> Result<(), String>,
+{
+    let mut current = read_full(reader, size)?;
+    let mut counter: u32 = 0;
+    loop {
+        let next = if current.len() == size { read_full(reader, size)? } else { vec![] };
+        let last = next.is_empty();
+        f(&current, counter, last)?;
+        if last {
+            return Ok(());
+        }
+        counter = counter.checked_add(1).ok_or("The input is too large".to_string())?;
+        current = next;
+    }
+}
+
+pub struct CryptImpl {}
+
+impl CryptImpl {
+    /// 加密: 写入文件头, 然后将明文按 `chunk_size` 分块加密 (最后一块可能为空)
+    fn encrypt(reader: &mut impl Read, writer: &mut impl Write, header: &Header, secret: &Secret) -> Result<(), String> {
+        let aad = header.to_bytes();
+        let cipher = Cipher::new(header.algorithm, &header.key(secret)?);
+        writer.write_all(&aad).map_err(|err| format!("{err}"))?;
+
+        for_each_chunk(reader, header.chunk_size as usize, |chunk, counter, last| {
+            let sealed = cipher.seal(&chunk_nonce(&header.nonce_prefix, counter, last), chunk, &aad)?;
+            writer.write_all(&sealed).map_err(|err| format!("{err}"))
+        })?;
+        writer.flush().map_err(|err| format!("{err}"))
+    }
+
+    /// 读取文件头并派生密钥, 返回文件头, 文件头的原始字节及密钥对应的加密算法
+    fn open(reader: &mut impl Read, secret: &Secret) -> Result<(Header, Vec<u8>, Cipher), String> {
+        let (header, aad) = Header::read(reader)?;
+        let cipher = Cipher::new(header.algorithm, &header.key(secret)?);
+        Ok((header, aad, cipher))
+    }
+
+    /// 解密文件头之后的所有块: 每一块都在写出前完成认证, 缺少最后一块 (文件被截断) 时报错
+    fn decrypt(reader: &mut impl Read, writer: &mut impl Write, header: &Header, aad: &[u8], cipher: &Cipher) -> Result<(), String> {
+        for_each_chunk(reader, header.chunk_size as usize + TAG_SIZE, |chunk, counter, last| {
+            if chunk.len() < TAG_SIZE {
+                return Err("The encrypted data is truncated".to_string());
+            }
+            // 被截断在块边界时, 最后读到的一块并不是以结束标志加密的, 认证会失败
+            let plain = cipher.open(&chunk_nonce(&header.nonce_prefix, counter, last), chunk, aad)?;
+            writer.write_all(&plain).map_err(|err| format!("{err}"))
+        })?;
+        writer.flush().map_err(|err| format!("{err}"))
+    }
+
+    /// 读取口令或密钥, 必须且只能指定一种
+    fn secret(options: &CryptOptions) -> Result<Secret, String> {
+        let read = |path: &String| fs::read(path).map_err(|err| format!("{path}: {err}"));
+        let secret = match (&options.password, &options.password_file, &options.key, &options.key_file) {
+            (Some(password), None, None, None) => Secret::Password(password.as_bytes().to_vec()),
+            // 只忽略口令文件末尾的一个换行, 与 `--hmac-key-file` 一致
+            (None, Some(path), None, None) => Secret::Password(strip_line_ending(read(path)?)),
+            (None, None, Some(key), None) => Secret::Key(HashImpl::decode_key(key.as_bytes().to_vec(), &options.key_encoding)?),
+            (None, None, None, Some(path)) => Secret::Key(HashImpl::decode_key(read(path)?, &options.key_encoding)?),
+            _ => return Err("Specify exactly one of '--password', '--password-file', '--key' and '--key-file'".to_string()),
+        };
+
+        match &secret {
+            Secret::Password(password) if password.is_empty() => Err("The password is empty".to_string()),
+            Secret::Key(key) if key.len() != KEY_SIZE => Err(format!("The key must be {KEY_SIZE} bytes, got {} bytes", key.len())),
+            _ => Ok(secret),
+        }
+    }
+
+    /// 确定输出路径: 加密时默认添加 `.enc` 扩展名, 解密时默认去掉 `.enc` 扩展名
+    fn output(options: &CryptOptions) -> Result<String, String> {
+        if let Some(output) = &options.output {
+            return Ok(output.clone());
+        }
+        if options.source == "-" {
+            return Err("'--output' is required when reading from stdin".to_string());
+        }
+
+        match (options.decrypt, options.source.strip_suffix(EXTENSION)) {
+            (false, _) => Ok(format!("{}{EXTENSION}", options.source)),
+            (true, Some(output)) if !output.is_empty() => Ok(output.to_string()),
+            (true, _) => Err(format!("Can not infer the output path (the source does not end with '{EXTENSION}'), use '--output'")),
+        }
+    }
+
+    fn run(options: &CryptOptions, output: &str) -> Result<String, String> {
+        let secret = CryptImpl::secret(options)?;
+        let algorithm = Algorithm::parse(&options.algorithm)?;
+        let mut reader: Box<dyn Read> = match options.source.as_str() {
+            "-" => Box::new(io::stdin().lock()),
+            source => Box::new(BufReader::new(File::open(source).map_err(|err| format!("{source}: {err}"))?)),
+        };
+        // 来源已确认存在, 输出不存在时无法解析其规范路径, 也就不可能是来源本身
+        if options.source != "-" {
+            if let (Ok(source), Ok(target)) = (fs::canonicalize(&options.source), fs::canonicalize(output)) {
+                if source == target {
+                    return Err("The output must not be the source file".to_string());
+                }
+            }
+        }
+        if !options.force && Path::new(output).exists() {
+            return Err(format!("'{output}' already exists, use '--force' to overwrite it"));
+        }
+        // 解密时先校验文件头并派生密钥, 再创建输出文件
+        let opened = match options.decrypt {
+            true => Some(CryptImpl::open(&mut reader, &secret)?),
+            false => None,
+        };
+
+        // 写入临时文件, 全部完成 (解密时最后一块通过认证) 后才替换输出, 失败时不会破坏已存在的输出
+        let temp = format!("{output}{TEMP_SUFFIX}");
+        let file = File::create(&temp).map_err(|err| format!("{temp}: {err}"))?;
+        let mut writer = BufWriter::new(file);
+        let result = match &opened {
+            Some((header, aad, cipher)) => CryptImpl::decrypt(&mut reader, &mut writer, header, aad, cipher)
+                .map(|_| format!("Decrypted to '{output}' ({})", header.algorithm.name())),
+            None => CryptImpl::encrypt(&mut reader, &mut writer, &Header::new(algorithm, &secret), &secret)
+                .map(|_| format!("Encrypted to '{output}' ({})", algorithm.name())),
+        };
+        drop(writer);
+
+        // 不保留不完整 (或未通过认证) 的输出
+        match result.and_then(|message| fs::rename(&temp, output).map(|_| message).map_err(|err| format!("{output}: {err}"))) {
+            Ok(message) => Ok(message),
+            Err(err) => {
+                let _ = fs::remove_file(&temp);
+                Err(err)
+            }
+        }
+    }
+
+    /// 处理 Command::Crypt 子命令
+    pub fn handle(options: CryptOptions) {
+        match options.decrypt {
+            // 解密时算法由文件头决定
+            true => println!("[Commands::Crypt] source = '{}', mode = 'decrypt'", options.source),
+            false => println!("[Commands::Crypt] source = '{}', mode = 'encrypt', algorithm = '{}'", options.source, options.algorithm),
+        }
+
+        let output = match CryptImpl::output(&options) {
+            Ok(output) => output,
+            Err(err) => return println!("Error: {err}"),
+        };
+        match CryptImpl::run(&options, &output) {
+            Ok(message) => println!("Ok: {message}"),
+            Err(err) => {
+                println!("Error: {err}");
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod unit_test {
+    use super::*;
+    use data_encoding::HEXLOWER;
+
+    /// 使用较小的块与 Argon2 参数加密, 返回密文
+    fn encrypt(data: &[u8], algorithm: Algorithm, secret: &Secret) -> Vec<u8> {
+        let mut header = Header::new(algorithm, secret);
+        header.chunk_size = 16;
+        if let Some(kdf) = &mut header.kdf {
+            kdf.memory = 64;
+            kdf.iterations = 1;
+        }
+        let mut output = vec![];
+        CryptImpl::encrypt(&mut &data[..], &mut output, &header, secret).unwrap();
+        output
+    }
+
+    fn decrypt(mut data: &[u8], secret: &Secret) -> Result<Vec<u8>, String> {
+        let (header, aad, cipher) = CryptImpl::open(&mut data, secret)?;
+        let mut output = vec![];
+        CryptImpl::decrypt(&mut data, &mut output, &header, &aad, &cipher).map(|_| output)
+    }
+
+    #[test]
+    fn round_trip_test() {
+        let password = Secret::Password(b"hunter2".to_vec());
+        let key = Secret::Key(vec![7; KEY_SIZE]);
+        for algorithm in [Algorithm::Aes256Gcm, Algorithm::ChaCha20Poly1305] {
+            for len in [0u8, 1, 16, 17, 100] {
+                let data: Vec<u8> = (0..len).collect();
+                for secret in [&password, &key] {
+                    let encrypted = encrypt(&data, algorithm, secret);
+                    // 每一块都带有 16 字节的标签, 空的输入也有一块
+                    let chunks = (len as usize).div_ceil(16).max(1);
+                    assert_eq!(encrypted.len() - Header::read(&mut &encrypted[..]).unwrap().1.len(), len as usize + chunks * TAG_SIZE);
+                    assert_eq!(decrypt(&encrypted, secret).unwrap(), data);
+                }
+            }
+        }
+        assert!(decrypt(&encrypt(b"data", Algorithm::Aes256Gcm, &password), &Secret::Password(b"hunter3".to_vec())).is_err());
+        assert!(decrypt(&encrypt(b"data", Algorithm::Aes256Gcm, &password), &key).is_err());
+    }
+
+    #[test]
+    fn tamper_test() {
+        let key = Secret::Key(vec![7; KEY_SIZE]);
+        let encrypted = encrypt(&[1u8; 40], Algorithm::ChaCha20Poly1305, &key);
+        let header_len = Header::read(&mut &encrypted[..]).unwrap().1.len();
+
+        // 修改文件头 (块大小) 或密文中的任意一个字节
+        for index in [header_len - 8, header_len, encrypted.len() - 1] {
+            let mut tampered = encrypted.clone();
+            tampered[index] ^= 1;
+            assert!(decrypt(&tampered, &key).is_err(), "{index}");
+        }
+        // 在块边界截断, 或交换两块的顺序
+        assert!(decrypt(&encrypted[..header_len + 2 * (16 + TAG_SIZE)], &key).is_err());
+        let mut swapped = encrypted[..header_len].to_vec();
+        swapped.extend_from_slice(&encrypted[header_len + 32..header_len + 64]);
+        swapped.extend_from_slice(&encrypted[header_len..header_len + 32]);
+        swapped.extend_from_slice(&encrypted[header_len + 64..]);
+        assert!(decrypt(&swapped, &key).is_err());
+        assert!(decrypt(b"RSTC", &key).is_err());
+    }
+
+    /// 文件头中过大的 Argon2 参数在派生密钥之前就被拒绝
+    #[test]
+    fn kdf_limit_test() {
+        let password = Secret::Password(b"hunter2".to_vec());
+        let mut header = Header::new(Algorithm::Aes256Gcm, &password);
+        header.kdf.as_mut().unwrap().memory = MAX_ARGON2_MEMORY + 1;
+        assert!(Header::read(&mut &header.to_bytes()[..]).unwrap_err().contains("exceed the limits"));
+
+        header.kdf.as_mut().unwrap().memory = ARGON2_MEMORY;
+        header.kdf.as_mut().unwrap().iterations = MAX_ARGON2_ITERATIONS + 1;
+        assert!(Header::read(&mut &header.to_bytes()[..]).is_err());
+        header.kdf.as_mut().unwrap().iterations = ARGON2_ITERATIONS;
+        assert!(Header::read(&mut &header.to_bytes()[..]).is_ok());
+    }
+
+    /// 解密失败时保留已存在的输出, 未指定 `--force` 时不覆盖已存在的输出
+    #[test]
+    fn run_test() {
+        let dir = std::env::temp_dir().join("rstool_crypt_run_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = |name: &str| dir.join(name).to_string_lossy().to_string();
+        fs::write(path("notes.txt"), "secret notes").unwrap();
+
+        let options = |source: &str, decrypt: bool, key: u8, force: bool| CryptOptions {
+            source: path(source),
+            output: None,
+            decrypt,
+            algorithm: "chacha20poly1305".to_string(),
+            password: None,
+            password_file: None,
+            key: Some(HEXLOWER.encode(&[key; KEY_SIZE])),
+            key_file: None,
+            key_encoding: "hex".to_string(),
+            force,
+        };
+        let run = |options: &CryptOptions| CryptImpl::run(options, &CryptImpl::output(options).unwrap());
+
+        assert!(run(&options("notes.txt", false, 1, false)).is_ok());
+        assert!(run(&options("notes.txt", false, 1, false)).unwrap_err().contains("already exists"));
+        assert!(run(&options("notes.txt.enc", true, 2, true)).is_err());
+        assert_eq!(fs::read(path("notes.txt")).unwrap(), b"secret notes");
+        assert!(!Path::new(&path("notes.txt.rstool-crypt")).exists());
+
+        fs::remove_file(path("notes.txt")).unwrap();
+        assert!(run(&options("notes.txt.enc", true, 1, false)).is_ok());
+        assert_eq!(fs::read(path("notes.txt")).unwrap(), b"secret notes");
+
+        // 来源不存在时报告来源缺失, 而不是误判为输出与来源相同
+        let err = run(&options("missing.txt", false, 1, false)).unwrap_err();
+        assert!(err.contains("missing.txt") && !err.contains("must not be the source"), "{err}");
+        let same = CryptOptions { output: Some(path("notes.txt")), force: true, ..options("notes.txt", false, 1, true) };
+        assert!(CryptImpl::run(&same, &path("notes.txt")).unwrap_err().contains("must not be the source"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// 口令文件只去掉末尾的一个换行
+    #[test]
+    fn password_file_test() {
+        let dir = std::env::temp_dir().join("rstool_crypt_password_file_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let password = |content: &[u8]| {
+            let path = dir.join("password.txt");
+            fs::write(&path, content).unwrap();
+            let options = CryptOptions {
+                source: "-".to_string(),
+                output: None,
+                decrypt: false,
+                algorithm: "aes256gcm".to_string(),
+                password: None,
+                password_file: Some(path.to_string_lossy().to_string()),
+                key: None,
+                key_file: None,
+                key_encoding: "hex".to_string(),
+                force: false,
+            };
+            match CryptImpl::secret(&options).unwrap() {
+                Secret::Password(password) => password,
+                Secret::Key(_) => unreachable!(),
+            }
+        };
+
+        assert_eq!(password(b"secret\n"), b"secret");
+        assert_eq!(password(b"secret\r\n"), b"secret");
+        assert_eq!(password(b"secret\n\n"), b"secret\n");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,350 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::hash::Hash;
+use std::io::{BufReader, ErrorKind, Read};
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+use crate::hash::{default_jobs, digest_file, format_size, parallel_map, supports_algorithm};
+
+/// 第二轮分组时读取的文件开头字节数
+const PARTIAL_SIZE: u64 = 64 * 1024;
+/// 第二轮分组使用的快速 (非加密) 算法
+const PARTIAL_ALGORITHM: &str = "xxh3";
+
+/// Arguments of `Commands::Dedupe`
+pub struct DedupeOptions {
+    pub sources: Vec<String>,
+    pub algorithm: String,
+    pub action: String,
+    pub dry_run: bool,
+    pub min_size: u64,
+    pub jobs: Option<usize>,
+}
+
+/// 对重复文件的处理方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Action {
+    /// 仅输出重复文件
+    Report,
+    /// 将重复文件替换为指向保留文件的硬链接
+    Hardlink,
+    /// 删除重复文件, 只保留一份
+    Delete,
+}
+
+impl Action {
+    fn parse(action: &str) -> Result<Action, String> {
+        match action.to_lowercase().as_str() {
+            "report" => Ok(Action::Report),
+            "hardlink" => Ok(Action::Hardlink),
+            "delete" => Ok(Action::Delete),
+            _ => Err(format!("Invalid action '{action}' (Expect one of: report, hardlink, delete)")),
+        }
+    }
+}
+
+struct FileInfo {
+    path: PathBuf,
+    size: u64,
+}
+
+pub struct DedupeImpl {}
+
+impl DedupeImpl {
+    /// 收集所有来源中不小于 `min_size` 的普通文件 (不跟随符号链接), 同一文件的多个硬链接只保留一个, 按路径排序
+    /// 来源重叠 (e.g. 'dedupe a a', 'dedupe . ./sub') 时同一文件会被遍历多次, 按规范化路径去重, 否则会把唯一的一份当作重复文件删除
+    fn collect(sources: &[String], min_size: u64) -> Result<Vec<FileInfo>, String> {
+        let mut files = vec![];
+        let mut seen = HashSet::new();
+        let mut seen_paths = HashSet::new();
+
+        for source in sources {
+            for item in WalkDir::new(source) {
+                let item = item.map_err(|err| format!("{err}"))?;
+                if !item.file_type().is_file() {
+                    continue;
+                }
+
+                let metadata = item.metadata().map_err(|err| format!("{err}"))?;
+                if metadata.len() < min_size {
+                    continue;
+                }
+                let canonical = fs::canonicalize(item.path()).map_err(|err| format!("{}: {err}", item.path().display()))?;
+                if !seen_paths.insert(canonical) {
+                    continue;
+                }
+                if let Some(id) = file_id(&metadata) {
+                    if !seen.insert(id) {
+                        continue;
+                    }
+                }
+                files.push(FileInfo { path: item.into_path(), size: metadata.len() });
+            }
+        }
+
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(files)
+    }
+
+    /// 按照 `key` 将每一组再细分, 丢弃只剩一个文件的组; 读取失败的文件会输出警告并跳过
+    fn regroup<K, F>(groups: Vec<Vec<FileInfo>>, jobs: usize, key: F) -> Vec<Vec<FileInfo>>
+    where
+        K: Eq + Hash + Send,
+        F: Fn(&FileInfo) -> Result<K, String> + Sync,
+    {
+        let mut result = vec![];
+
+        for group in groups {
+            // 单个文件的读取错误不应中断整个流程, 因此不在 `parallel_map` 中返回错误
+            let keys = parallel_map(&group, jobs, |file| Ok(key(file))).unwrap_or_default();
+
+            let mut buckets: HashMap<K, Vec<FileInfo>> = HashMap::new();
+            for (file, key) in group.into_iter().zip(keys) {
+                match key {
+                    Ok(key) => buckets.entry(key).or_default().push(file),
+                    Err(err) => println!("WARNING: {}: {err}", file.path.display()),
+                }
+            }
+
+            // 保持组内及组间的路径顺序
+            let mut split: Vec<Vec<FileInfo>> = buckets.into_values().filter(|bucket| bucket.len() > 1).collect();
+            split.sort_by(|a, b| a[0].path.cmp(&b[0].path));
+            result.extend(split);
+        }
+
+        result
+    }
+
+    /// 查找重复文件: 先按大小分组, 再按文件开头部分的快速摘要分组, 最后按完整摘要分组
+    fn find_duplicates(files: Vec<FileInfo>, algorithm: &str, jobs: usize) -> Vec<Vec<FileInfo>> {
+        let by_size = DedupeImpl::regroup(vec![files], 1, |file| Ok(file.size));
+        let by_partial = DedupeImpl::regroup(by_size, jobs, |file| digest_file(PARTIAL_ALGORITHM, &file.path, Some(PARTIAL_SIZE)));
+
+        let mut sets = DedupeImpl::regroup(by_partial, jobs, |file| digest_file(algorithm, &file.path, None));
+        // 浪费空间最多的排在前面
+        sets.sort_by(|a, b| wasted(b).cmp(&wasted(a)).then_with(|| a[0].path.cmp(&b[0].path)));
+        sets
+    }
+
+    /// 将 `duplicate` 替换为指向 `keep` 的硬链接: 先创建临时链接再重命名, 失败时不会丢失原文件
+    fn hardlink(keep: &Path, duplicate: &Path) -> Result<(), String> {
+        let mut temp = duplicate.as_os_str().to_owned();
+        temp.push(".rstool-dedupe");
+        let temp = PathBuf::from(temp);
+
+        fs::hard_link(keep, &temp).map_err(|err| format!("{err}"))?;
+        fs::rename(&temp, duplicate).map_err(|err| {
+            let _ = fs::remove_file(&temp);
+            format!("{err}")
+        })
+    }
+
+    /// 逐字节比较两个文件的内容是否相同 (非加密算法可以构造碰撞, 删除或替换前不能只依赖摘要)
+    fn same_content(a: &Path, b: &Path) -> Result<bool, String> {
+        let mut a = BufReader::new(File::open(a).map_err(|err| format!("{err}"))?);
+        let mut b = BufReader::new(File::open(b).map_err(|err| format!("{err}"))?);
+        let (mut buffer_a, mut buffer_b) = (vec![0u8; PARTIAL_SIZE as usize], vec![0u8; PARTIAL_SIZE as usize]);
+
+        loop {
+            let len = read_full(&mut a, &mut buffer_a)?;
+            if len != read_full(&mut b, &mut buffer_b)? || buffer_a[..len] != buffer_b[..len] {
+                return Ok(false);
+            }
+            if len == 0 {
+                return Ok(true);
+            }
+        }
+    }
+
+    /// 确认 `duplicate` 与 `keep` 的内容相同
+    fn verify(keep: &Path, duplicate: &Path) -> Result<(), String> {
+        match DedupeImpl::same_content(keep, duplicate)? {
+            true => Ok(()),
+            false => Err(format!("content differs from {}", keep.display())),
+        }
+    }
+
+    /// 处理一组重复文件 (保留第一个), 返回处理成功的文件数
+    /// 删除或替换为硬链接之前会逐字节确认内容与保留的文件相同
+    fn apply(set: &[FileInfo], action: Action, dry_run: bool) -> usize {
+        let keep = &set[0].path;
+        let mut done = 0;
+
+        for duplicate in &set[1..] {
+            let path = duplicate.path.display();
+            let (verb, result) = match (action, dry_run) {
+                (Action::Report, _) => continue,
+                (Action::Hardlink, true) => ("would link", Ok(())),
+                (Action::Delete, true) => ("would delete", Ok(())),
+                (Action::Hardlink, false) => ("linked", DedupeImpl::verify(keep, &duplicate.path).and_then(|_| DedupeImpl::hardlink(keep, &duplicate.path))),
+                (Action::Delete, false) => ("deleted", DedupeImpl::verify(keep, &duplicate.path).and_then(|_| fs::remove_file(&duplicate.path).map_err(|err| format!("{err}")))),
+            };
+
+            match result {
+                Ok(_) => {
+                    done += 1;
+                    println!("  {verb}: {path}");
+                }
+                Err(err) => println!("  failed: {path} ({err})"),
+            }
+        }
+
+        done
+    }
+
+    /// 处理 Command::Dedupe 子命令
+    pub fn handle(options: DedupeOptions) {
+        let DedupeOptions { sources, algorithm, action, dry_run, min_size, jobs } = options;
+        println!("[Commands::Dedupe] sources = '{}', algorithm = '{algorithm}', action = '{action}', dry_run = '{dry_run}'", sources.join(", "));
+
+        let action = match Action::parse(&action) {
+            Ok(action) => action,
+            Err(err) => return println!("Error: {err}"),
+        };
+        if !supports_algorithm(&algorithm) {
+            return println!("Error: Invalid algorithm '{algorithm}'");
+        }
+        if let Some(source) = sources.iter().find(|source| !Path::new(source).is_dir()) {
+            return println!("Error: '{source}' is not a directory");
+        }
+
+        let files = match DedupeImpl::collect(&sources, min_size.max(1)) {
+            Ok(files) => files,
+            Err(err) => return println!("Error: {err}"),
+        };
+        let jobs = jobs.filter(|jobs| *jobs > 0).unwrap_or_else(default_jobs);
+        let sets = DedupeImpl::find_duplicates(files, &algorithm, jobs);
+
+        let (mut duplicates, mut total_wasted, mut processed) = (0, 0, 0);
+        for (index, set) in sets.iter().enumerate() {
+            println!("Set {} ({} files, {} each, {} wasted):", index + 1, set.len(), format_size(set[0].size), format_size(wasted(set)));
+            for file in set {
+                println!("  {}", file.path.display());
+            }
+            processed += DedupeImpl::apply(set, action, dry_run);
+            duplicates += set.len() - 1;
+            total_wasted += wasted(set);
+        }
+
+        let summary = format!("{} duplicate set(s), {duplicates} duplicate file(s), {} wasted", sets.len(), format_size(total_wasted));
+        match (action, dry_run) {
+            (Action::Report, _) | (_, true) => println!("Ok: {summary}"),
+            (Action::Hardlink, false) => println!("Ok: {summary}, {processed} file(s) replaced with hardlinks"),
+            (Action::Delete, false) => println!("Ok: {summary}, {processed} file(s) deleted"),
+        }
+    }
+}
+
+/// 一组重复文件浪费的空间 (除保留的一份外)
+fn wasted(set: &[FileInfo]) -> u64 {
+    set[0].size * (set.len() as u64 - 1)
+}
+
+/// 尽量读满 `buffer`, 只有到达文件末尾时返回的长度才会小于 `buffer` 的长度
+fn read_full(reader: &mut impl Read, buffer: &mut [u8]) -> Result<usize, String> {
+    let mut len = 0;
+    while len < buffer.len() {
+        match reader.read(&mut buffer[len..]) {
+            Ok(0) => break,
+            Ok(read) => len += read,
+            Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+            Err(err) => return Err(format!("{err}")),
+        }
+    }
+    Ok(len)
+}
+
+/// 文件的唯一标识 (设备号, inode), 用于识别指向同一文件的硬链接
+#[cfg(unix)]
+fn file_id(metadata: &fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+/// Windows 上需要打开文件才能获取文件索引, 因此不识别硬链接
+#[cfg(not(unix))]
+fn file_id(_metadata: &fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+#[cfg(test)]
+mod unit_test {
+    use super::*;
+
+    #[test]
+    fn find_duplicates_test() {
+        let dir = std::env::temp_dir().join("rstool_dedupe_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("sub")).unwrap();
+
+        // 大小相同且开头 64 KiB 相同, 只有完整摘要不同
+        let mut large = vec![1u8; PARTIAL_SIZE as usize + 10];
+        fs::write(dir.join("a.bin"), &large).unwrap();
+        fs::write(dir.join("sub").join("a.bin"), &large).unwrap();
+        *large.last_mut().unwrap() = 2;
+        fs::write(dir.join("b.bin"), &large).unwrap();
+        fs::write(dir.join("c.txt"), "hello").unwrap();
+        fs::write(dir.join("d.txt"), "hello").unwrap();
+        fs::write(dir.join("e.txt"), "world").unwrap();
+
+        let files = DedupeImpl::collect(&[dir.to_string_lossy().to_string()], 1).unwrap();
+        let sets = DedupeImpl::find_duplicates(files, "blake3", 4);
+        let names: Vec<Vec<String>> = sets.iter()
+            .map(|set| set.iter().map(|file| file.path.strip_prefix(&dir).unwrap().to_string_lossy().replace('\\', "/")).collect())
+            .collect();
+        assert_eq!(names, vec![vec!["a.bin", "sub/a.bin"], vec!["c.txt", "d.txt"]]);
+
+        // 替换为硬链接后不再重复计算
+        DedupeImpl::apply(&sets[0], Action::Hardlink, false);
+        let files = DedupeImpl::collect(&[dir.to_string_lossy().to_string()], 1).unwrap();
+        assert_eq!(DedupeImpl::find_duplicates(files, "blake3", 4).len(), if cfg!(unix) { 1 } else { 2 });
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// 来源重叠时同一文件只收集一次, 不会被当作自身的重复文件
+    #[test]
+    fn overlapping_sources_test() {
+        let dir = std::env::temp_dir().join("rstool_dedupe_overlap_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("sub").join("only.txt"), "hello").unwrap();
+
+        let source = dir.to_string_lossy().to_string();
+        let sub = dir.join("sub").to_string_lossy().to_string();
+        let files = DedupeImpl::collect(&[source.clone(), source, sub], 1).unwrap();
+        assert_eq!(files.len(), 1);
+        assert!(DedupeImpl::find_duplicates(files, "crc32", 1).is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// 摘要相同但内容不同的文件不会被删除
+    #[test]
+    fn verify_test() {
+        let dir = std::env::temp_dir().join("rstool_dedupe_verify_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let large = vec![1u8; PARTIAL_SIZE as usize + 10];
+        let mut other = large.clone();
+        *other.last_mut().unwrap() = 2;
+        fs::write(dir.join("a.bin"), &large).unwrap();
+        fs::write(dir.join("b.bin"), &large).unwrap();
+        fs::write(dir.join("c.bin"), &other).unwrap();
+        fs::write(dir.join("d.bin"), &large[..10]).unwrap();
+
+        assert!(DedupeImpl::verify(&dir.join("a.bin"), &dir.join("b.bin")).is_ok());
+        assert!(DedupeImpl::verify(&dir.join("a.bin"), &dir.join("c.bin")).is_err());
+        assert!(DedupeImpl::verify(&dir.join("a.bin"), &dir.join("d.bin")).is_err());
+
+        // 模拟摘要碰撞: 把内容不同的文件放在同一组中
+        let set = vec![
+            FileInfo { path: dir.join("a.bin"), size: large.len() as u64 },
+            FileInfo { path: dir.join("c.bin"), size: other.len() as u64 },
+        ];
+        assert_eq!(DedupeImpl::apply(&set, Action::Delete, false), 0);
+        assert!(dir.join("c.bin").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
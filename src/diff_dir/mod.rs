@@ -0,0 +1,309 @@
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fs;
+use std::path::Path;
+use data_encoding::HEXLOWER;
+use crate::hash::{collect_files, default_jobs, is_xof, json_string, normalize_entry_path, parse_line, supports_algorithm, HashImpl, HasherConfig, ManifestEntry};
+
+/// 未指定算法且两侧都是目录时使用的算法
+const DEFAULT_ALGORITHM: &str = "sha256";
+
+/// Arguments of `Commands::DiffDir`
+pub struct DiffDirOptions {
+    pub left: String,
+    pub right: String,
+    pub algorithm: Option<String>,
+    pub json: bool,
+    pub jobs: Option<usize>,
+}
+
+/// 相对路径 -> 十六进制摘要 (小写), 按路径排序
+type Tree = BTreeMap<String, String>;
+/// 算法名及可扩展输出函数的输出字节数
+type Algorithm = (String, Option<usize>);
+
+/// 两棵目录树的差异, 路径均按字节序排序
+#[derive(Debug, Default, PartialEq)]
+struct TreeDiff {
+    added: Vec<String>,
+    removed: Vec<String>,
+    modified: Vec<String>,
+    /// (原路径, 新路径)
+    moved: Vec<(String, String)>,
+    unchanged: usize,
+}
+
+impl TreeDiff {
+    fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty() && self.moved.is_empty()
+    }
+}
+
+/// 比较的一侧: 目录或清单文件 (每一行及其所用的算法)
+enum Side {
+    Directory(String),
+    Manifest(Vec<(Algorithm, ManifestEntry)>),
+}
+
+pub struct DiffDirImpl {}
+
+impl DiffDirImpl {
+    /// 区分目录与清单, 清单在此读取并确定每一行的算法 (算法标签或摘要长度, 与 `--check` 一致, 长度有歧义时参考 `hint`)
+    fn open(source: &str, hint: &str) -> Result<Side, String> {
+        let path = Path::new(source);
+        if path.is_dir() {
+            return Ok(Side::Directory(source.to_string()));
+        }
+        if !path.is_file() {
+            return Err(format!("{source}: No such file or directory"));
+        }
+
+        let content = fs::read_to_string(path).map_err(|err| format!("{source}: {err}"))?;
+        let mut entries = vec![];
+        for line in content.lines().filter(|line| !line.trim().is_empty() && !line.starts_with('#')) {
+            let entry = parse_line(line).ok_or(format!("{source}: Improperly formatted line '{line}'"))?;
+            let name = HashImpl::resolve_algorithm(&entry, hint).ok_or(format!("{}: Unknown algorithm", entry.path))?;
+            let length = is_xof(&name).then_some(entry.digest.len() / 2);
+            entries.push(((name, length), entry));
+        }
+        if entries.is_empty() {
+            return Err(format!("No properly formatted checksum lines found in '{source}'"));
+        }
+
+        Ok(Side::Manifest(entries))
+    }
+
+    /// 确定比较所用的算法 (及可扩展输出函数的输出字节数):
+    /// - 有清单时从所有清单共有的算法中选择 (清单可以包含多个算法, e.g. `hash -a md5,sha256` 的输出),
+    ///   优先使用 `algorithm`, 否则使用第一个清单中最先出现的算法
+    /// - 否则使用 `algorithm`, 默认为 sha256
+    fn resolve_algorithm(sides: [&Side; 2], algorithm: Option<&str>) -> Result<Algorithm, String> {
+        let hint = algorithm.unwrap_or(DEFAULT_ALGORITHM).to_lowercase();
+        let manifests: Vec<Vec<&Algorithm>> = sides.iter()
+            .filter_map(|side| match side {
+                Side::Manifest(lines) => Some(lines.iter().map(|(algorithm, _)| algorithm).collect()),
+                Side::Directory(_) => None,
+            })
+            .collect();
+
+        let Some(first) = manifests.first() else {
+            return match supports_algorithm(&hint) {
+                true => Ok((hint, None)),
+                false => Err(format!("Invalid algorithm '{hint}'")),
+            };
+        };
+        let common: Vec<&Algorithm> = first.iter()
+            .filter(|candidate| manifests.iter().all(|algorithms| algorithms.contains(candidate)))
+            .copied()
+            .collect();
+
+        match common.iter().find(|(name, _)| algorithm.is_some() && *name == hint).or(common.first()) {
+            Some(resolved) => Ok((*resolved).clone()),
+            None => Err("The manifests have no algorithm in common".to_string()),
+        }
+    }
+
+    /// 计算一侧所有文件的摘要, 清单只使用 `algorithm` 对应的行
+    /// 清单中同一路径在该算法下出现多次, 或有路径缺少该算法的摘要时返回错误
+    fn load(side: Side, algorithm: &str, output_length: Option<usize>, jobs: usize) -> Result<Tree, String> {
+        match side {
+            Side::Manifest(lines) => {
+                let mut tree = Tree::new();
+                let mut paths = HashSet::new();
+                for ((name, length), entry) in lines {
+                    let path = normalize_entry_path(&entry.path);
+                    if name != algorithm || length != output_length {
+                        paths.insert(path);
+                        continue;
+                    }
+                    if tree.insert(path.clone(), entry.digest).is_some() {
+                        return Err(format!("{path}: Duplicate path in the manifest"));
+                    }
+                }
+                match paths.into_iter().find(|path| !tree.contains_key(path)) {
+                    Some(path) => Err(format!("{path}: No '{algorithm}' digest in the manifest")),
+                    None => Ok(tree),
+                }
+            }
+            Side::Directory(root) => {
+                let files = collect_files(Path::new(&root))?;
+                let config = HasherConfig { output_length, ..HasherConfig::default() };
+                let results = HashImpl::hash_files(&[algorithm.to_string()], &config, files, jobs)?;
+                Ok(results.into_iter().map(|(path, digests)| (path, HEXLOWER.encode(&digests[0].1))).collect())
+            }
+        }
+    }
+
+    /// 比较两棵目录树: 同一路径摘要不同为修改; 仅在一侧出现的路径中, 摘要相同的一对视为移动 (重命名)
+    fn diff(left: &Tree, right: &Tree) -> TreeDiff {
+        let mut result = TreeDiff::default();
+
+        for (path, digest) in left {
+            match right.get(path) {
+                Some(other) if other == digest => result.unchanged += 1,
+                Some(_) => result.modified.push(path.clone()),
+                None => result.removed.push(path.clone()),
+            }
+        }
+
+        // 新增路径按摘要分组, 依次与摘要相同的删除路径配对
+        let mut added: HashMap<&str, VecDeque<&str>> = HashMap::new();
+        for (path, digest) in right.iter().filter(|(path, _)| !left.contains_key(*path)) {
+            added.entry(digest).or_default().push_back(path);
+        }
+        let mut moved_to = HashSet::new();
+        result.removed.retain(|path| {
+            match added.get_mut(left[path].as_str()).and_then(|paths| paths.pop_front()) {
+                Some(target) => {
+                    result.moved.push((path.clone(), target.to_string()));
+                    moved_to.insert(target);
+                    false
+                }
+                None => true,
+            }
+        });
+        result.added = right.keys()
+            .filter(|path| !left.contains_key(*path) && !moved_to.contains(&path.as_str()))
+            .cloned()
+            .collect();
+
+        result
+    }
+
+    fn format_json(diff: &TreeDiff) -> String {
+        let list = |paths: &[String]| paths.iter().map(|path| json_string(path)).collect::<Vec<_>>().join(", ");
+        let moved = diff.moved.iter()
+            .map(|(from, to)| format!("{{\"from\": {}, \"to\": {}}}", json_string(from), json_string(to)))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        format!(
+            "{{\n  \"added\": [{}],\n  \"removed\": [{}],\n  \"modified\": [{}],\n  \"moved\": [{moved}],\n  \"unchanged\": {}\n}}",
+            list(&diff.added), list(&diff.removed), list(&diff.modified), diff.unchanged
+        )
+    }
+
+    fn compare(options: &DiffDirOptions) -> Result<TreeDiff, String> {
+        let hint = options.algorithm.as_deref().unwrap_or(DEFAULT_ALGORITHM);
+        let left = DiffDirImpl::open(&options.left, hint)?;
+        let right = DiffDirImpl::open(&options.right, hint)?;
+        let (algorithm, output_length) = DiffDirImpl::resolve_algorithm([&left, &right], options.algorithm.as_deref())?;
+        let jobs = options.jobs.filter(|jobs| *jobs > 0).unwrap_or_else(default_jobs);
+
+        let left = DiffDirImpl::load(left, &algorithm, output_length, jobs)?;
+        let right = DiffDirImpl::load(right, &algorithm, output_length, jobs)?;
+        Ok(DiffDirImpl::diff(&left, &right))
+    }
+
+    /// 处理 Command::DiffDir 子命令
+    /// 输出 JSON 时 stdout 只包含 JSON 本身 (错误输出到 stderr), 两侧不一致时同样以退出码 1 结束
+    pub fn handle(options: DiffDirOptions) {
+        if !options.json {
+            println!(
+                "[Commands::DiffDir] left = '{}', right = '{}', algorithm = '{}'",
+                options.left, options.right, options.algorithm.clone().unwrap_or("auto".to_string())
+            );
+        }
+
+        let diff = match DiffDirImpl::compare(&options) {
+            Ok(diff) => diff,
+            Err(err) => {
+                match options.json {
+                    true => eprintln!("Error: {err}"),
+                    false => println!("Error: {err}"),
+                }
+                std::process::exit(1);
+            }
+        };
+
+        if options.json {
+            println!("{}", DiffDirImpl::format_json(&diff));
+            if !diff.is_empty() {
+                std::process::exit(1);
+            }
+            return;
+        }
+        for path in &diff.modified {
+            println!("MODIFIED: {path}");
+        }
+        for (from, to) in &diff.moved {
+            println!("MOVED: {from} -> {to}");
+        }
+        for path in &diff.removed {
+            println!("REMOVED: {path}");
+        }
+        for path in &diff.added {
+            println!("ADDED: {path}");
+        }
+        println!(
+            "Summary: {} unchanged, {} added, {} removed, {} modified, {} moved",
+            diff.unchanged, diff.added.len(), diff.removed.len(), diff.modified.len(), diff.moved.len()
+        );
+
+        if diff.is_empty() {
+            println!("Ok: Identical ({} files)", diff.unchanged);
+        } else {
+            println!("Error: Directories differ");
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod unit_test {
+    use super::*;
+
+    fn tree(items: &[(&str, &str)]) -> Tree {
+        items.iter().map(|(path, digest)| (path.to_string(), digest.to_string())).collect()
+    }
+
+    #[test]
+    fn diff_test() {
+        let left = tree(&[("a", "1"), ("b", "2"), ("c", "3"), ("d", "4"), ("e", "4")]);
+        let right = tree(&[("a", "1"), ("b", "9"), ("x/c", "3"), ("y", "4"), ("z", "5")]);
+
+        let diff = DiffDirImpl::diff(&left, &right);
+        assert_eq!(diff, TreeDiff {
+            added: vec!["z".to_string()],
+            removed: vec!["e".to_string()],
+            modified: vec!["b".to_string()],
+            moved: vec![("c".to_string(), "x/c".to_string()), ("d".to_string(), "y".to_string())],
+            unchanged: 1,
+        });
+        assert!(DiffDirImpl::diff(&left, &left).is_empty());
+    }
+
+    #[test]
+    fn compare_test() {
+        let dir = std::env::temp_dir().join("rstool_diff_dir_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("left").join("sub")).unwrap();
+        fs::create_dir_all(dir.join("right")).unwrap();
+        fs::write(dir.join("left").join("sub").join("a.txt"), "hello\n").unwrap();
+        fs::write(dir.join("right").join("a.txt"), "hello\n").unwrap();
+        // 'hello\n' 的 sha1
+        fs::write(dir.join("left.sha1"), "f572d396fae9206628714fb2ce00f72e94f2258f  ./sub/a.txt\n").unwrap();
+
+        let options = |left: &str, right: &str| DiffDirOptions {
+            left: dir.join(left).to_string_lossy().to_string(),
+            right: dir.join(right).to_string_lossy().to_string(),
+            algorithm: None,
+            json: false,
+            jobs: Some(2),
+        };
+        let moved = vec![("sub/a.txt".to_string(), "a.txt".to_string())];
+        assert_eq!(DiffDirImpl::compare(&options("left", "right")).unwrap().moved, moved);
+        assert_eq!(DiffDirImpl::compare(&options("left.sha1", "right")).unwrap().moved, moved);
+        assert!(DiffDirImpl::compare(&options("left.sha1", "left")).unwrap().is_empty());
+        assert!(DiffDirImpl::compare(&options("left", "missing")).is_err());
+
+        // 多算法清单: 使用两侧共有的算法
+        fs::write(dir.join("multi.txt"), "MD5 (sub/a.txt) = b1946ac92492d2347c6235b4d2611184\nSHA1 (sub/a.txt) = f572d396fae9206628714fb2ce00f72e94f2258f\n").unwrap();
+        assert!(DiffDirImpl::compare(&options("multi.txt", "left")).unwrap().is_empty());
+        assert!(DiffDirImpl::compare(&options("multi.txt", "left.sha1")).unwrap().is_empty());
+        // 同一路径在同一算法下出现两次
+        fs::write(dir.join("duplicate.sha1"), "f572d396fae9206628714fb2ce00f72e94f2258f  sub/a.txt\n0000000000000000000000000000000000000000  ./sub/a.txt\n").unwrap();
+        assert!(DiffDirImpl::compare(&options("duplicate.sha1", "left")).unwrap_err().contains("Duplicate path"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
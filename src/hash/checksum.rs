@@ -0,0 +1,424 @@
+use crate::hash::hasher::StreamHasher;
+
+// 非加密校验和 / 哈希 (CRC32, CRC32C, Adler-32, xxHash, MurmurHash3), 种子均为 0
+// 输出与各自的参考实现一致: 32/64/128 位整数按大端序输出 (e.g. CRC32 为 8 位十六进制), MurmurHash3 x64_128 按参考实现写出的字节序输出
+
+/// CRC-32 (IEEE 802.3, 与 zip/gzip 中的校验值相同)
+pub struct Crc32(crc32fast::Hasher);
+
+impl Crc32 {
+    pub fn new() -> Crc32 {
+        Crc32(crc32fast::Hasher::new())
+    }
+}
+
+impl StreamHasher for Crc32 {
+    fn update(&mut self, data: &[u8]) {
+        self.0.update(data);
+    }
+
+    fn output_size(&self) -> usize {
+        4
+    }
+
+    fn finalize(self: Box<Self>) -> Vec<u8> {
+        self.0.finalize().to_be_bytes().to_vec()
+    }
+}
+
+/// CRC-32C (Castagnoli)
+pub struct Crc32c(u32);
+
+impl Crc32c {
+    pub fn new() -> Crc32c {
+        Crc32c(0)
+    }
+}
+
+impl StreamHasher for Crc32c {
+    fn update(&mut self, data: &[u8]) {
+        self.0 = crc32c::crc32c_append(self.0, data);
+    }
+
+    fn output_size(&self) -> usize {
+        4
+    }
+
+    fn finalize(self: Box<Self>) -> Vec<u8> {
+        self.0.to_be_bytes().to_vec()
+    }
+}
+
+/// Adler-32
+pub struct Adler32(adler::Adler32);
+
+impl Adler32 {
+    pub fn new() -> Adler32 {
+        Adler32(adler::Adler32::new())
+    }
+}
+
+impl StreamHasher for Adler32 {
+    fn update(&mut self, data: &[u8]) {
+        self.0.write_slice(data);
+    }
+
+    fn output_size(&self) -> usize {
+        4
+    }
+
+    fn finalize(self: Box<Self>) -> Vec<u8> {
+        self.0.checksum().to_be_bytes().to_vec()
+    }
+}
+
+/// xxHash32
+pub struct Xxh32(xxhash_rust::xxh32::Xxh32);
+
+impl Xxh32 {
+    pub fn new() -> Xxh32 {
+        Xxh32(xxhash_rust::xxh32::Xxh32::new(0))
+    }
+}
+
+impl StreamHasher for Xxh32 {
+    fn update(&mut self, data: &[u8]) {
+        self.0.update(data);
+    }
+
+    fn output_size(&self) -> usize {
+        4
+    }
+
+    fn finalize(self: Box<Self>) -> Vec<u8> {
+        self.0.digest().to_be_bytes().to_vec()
+    }
+}
+
+/// xxHash64
+pub struct Xxh64(xxhash_rust::xxh64::Xxh64);
+
+impl Xxh64 {
+    pub fn new() -> Xxh64 {
+        Xxh64(xxhash_rust::xxh64::Xxh64::new(0))
+    }
+}
+
+impl StreamHasher for Xxh64 {
+    fn update(&mut self, data: &[u8]) {
+        self.0.update(data);
+    }
+
+    fn output_size(&self) -> usize {
+        8
+    }
+
+    fn finalize(self: Box<Self>) -> Vec<u8> {
+        self.0.digest().to_be_bytes().to_vec()
+    }
+}
+
+/// XXH3 (64 位或 128 位输出)
+pub struct Xxh3 {
+    state: Box<xxhash_rust::xxh3::Xxh3>,
+    wide: bool,
+}
+
+impl Xxh3 {
+    pub fn new(wide: bool) -> Xxh3 {
+        Xxh3 { state: Box::default(), wide }
+    }
+}
+
+impl StreamHasher for Xxh3 {
+    fn update(&mut self, data: &[u8]) {
+        self.state.update(data);
+    }
+
+    fn output_size(&self) -> usize {
+        if self.wide { 16 } else { 8 }
+    }
+
+    fn finalize(self: Box<Self>) -> Vec<u8> {
+        if self.wide {
+            self.state.digest128().to_be_bytes().to_vec()
+        } else {
+            self.state.digest().to_be_bytes().to_vec()
+        }
+    }
+}
+
+// --------------------- MurmurHash3 ---------------------
+/// MurmurHash3 x86_32
+pub struct Murmur3_32 {
+    h: u32,
+    /// 不足一个块 (4 字节) 的剩余数据
+    tail: Vec<u8>,
+    len: u64,
+}
+
+impl Murmur3_32 {
+    const C1: u32 = 0xcc9e2d51;
+    const C2: u32 = 0x1b873593;
+
+    pub fn new() -> Murmur3_32 {
+        Murmur3_32::with_seed(0)
+    }
+
+    pub fn with_seed(seed: u32) -> Murmur3_32 {
+        Murmur3_32 { h: seed, tail: Vec::with_capacity(4), len: 0 }
+    }
+
+    fn mix_k(k: u32) -> u32 {
+        k.wrapping_mul(Self::C1).rotate_left(15).wrapping_mul(Self::C2)
+    }
+
+    fn block(&mut self, block: &[u8]) {
+        let k = u32::from_le_bytes([block[0], block[1], block[2], block[3]]);
+        self.h ^= Self::mix_k(k);
+        self.h = self.h.rotate_left(13).wrapping_mul(5).wrapping_add(0xe6546b64);
+    }
+}
+
+impl StreamHasher for Murmur3_32 {
+    fn update(&mut self, mut data: &[u8]) {
+        self.len += data.len() as u64;
+
+        // 先补齐上次剩余的数据
+        if !self.tail.is_empty() {
+            let take = (4 - self.tail.len()).min(data.len());
+            self.tail.extend_from_slice(&data[..take]);
+            data = &data[take..];
+            if self.tail.len() < 4 {
+                return;
+            }
+            let block = std::mem::take(&mut self.tail);
+            self.block(&block);
+        }
+
+        let mut blocks = data.chunks_exact(4);
+        for block in blocks.by_ref() {
+            self.block(block);
+        }
+        self.tail.extend_from_slice(blocks.remainder());
+    }
+
+    fn output_size(&self) -> usize {
+        4
+    }
+
+    fn finalize(self: Box<Self>) -> Vec<u8> {
+        let mut h = self.h;
+
+        if !self.tail.is_empty() {
+            let k = self.tail.iter().rev().fold(0u32, |k, byte| (k << 8) | *byte as u32);
+            h ^= Self::mix_k(k);
+        }
+
+        // MurmurHash3 x86_32 只使用长度的低 32 位
+        h ^= self.len as u32;
+        h ^= h >> 16;
+        h = h.wrapping_mul(0x85ebca6b);
+        h ^= h >> 13;
+        h = h.wrapping_mul(0xc2b2ae35);
+        h ^= h >> 16;
+
+        h.to_be_bytes().to_vec()
+    }
+}
+
+/// MurmurHash3 x64_128
+pub struct Murmur3_128 {
+    h1: u64,
+    h2: u64,
+    /// 不足一个块 (16 字节) 的剩余数据
+    tail: Vec<u8>,
+    len: u64,
+}
+
+impl Murmur3_128 {
+    const C1: u64 = 0x87c37b91114253d5;
+    const C2: u64 = 0x4cf5ad432745937f;
+
+    pub fn new() -> Murmur3_128 {
+        Murmur3_128::with_seed(0)
+    }
+
+    /// 参考实现的种子为 32 位, 同时作为 h1 与 h2 的初始值
+    pub fn with_seed(seed: u32) -> Murmur3_128 {
+        Murmur3_128 { h1: seed as u64, h2: seed as u64, tail: Vec::with_capacity(16), len: 0 }
+    }
+
+    fn mix_k1(k1: u64) -> u64 {
+        k1.wrapping_mul(Self::C1).rotate_left(31).wrapping_mul(Self::C2)
+    }
+
+    fn mix_k2(k2: u64) -> u64 {
+        k2.wrapping_mul(Self::C2).rotate_left(33).wrapping_mul(Self::C1)
+    }
+
+    fn fmix(mut k: u64) -> u64 {
+        k ^= k >> 33;
+        k = k.wrapping_mul(0xff51afd7ed558ccd);
+        k ^= k >> 33;
+        k = k.wrapping_mul(0xc4ceb9fe1a85ec53);
+        k ^= k >> 33;
+        k
+    }
+
+    /// 按小端序将不超过 8 字节的数据读为 u64
+    fn read_le(bytes: &[u8]) -> u64 {
+        bytes.iter().rev().fold(0u64, |k, byte| (k << 8) | *byte as u64)
+    }
+
+    fn block(&mut self, block: &[u8]) {
+        self.h1 ^= Self::mix_k1(Self::read_le(&block[..8]));
+        self.h1 = self.h1.rotate_left(27).wrapping_add(self.h2).wrapping_mul(5).wrapping_add(0x52dce729);
+
+        self.h2 ^= Self::mix_k2(Self::read_le(&block[8..]));
+        self.h2 = self.h2.rotate_left(31).wrapping_add(self.h1).wrapping_mul(5).wrapping_add(0x38495ab5);
+    }
+}
+
+impl StreamHasher for Murmur3_128 {
+    fn update(&mut self, mut data: &[u8]) {
+        self.len += data.len() as u64;
+
+        // 先补齐上次剩余的数据
+        if !self.tail.is_empty() {
+            let take = (16 - self.tail.len()).min(data.len());
+            self.tail.extend_from_slice(&data[..take]);
+            data = &data[take..];
+            if self.tail.len() < 16 {
+                return;
+            }
+            let block = std::mem::take(&mut self.tail);
+            self.block(&block);
+        }
+
+        let mut blocks = data.chunks_exact(16);
+        for block in blocks.by_ref() {
+            self.block(block);
+        }
+        self.tail.extend_from_slice(blocks.remainder());
+    }
+
+    fn output_size(&self) -> usize {
+        16
+    }
+
+    fn finalize(self: Box<Self>) -> Vec<u8> {
+        let (mut h1, mut h2) = (self.h1, self.h2);
+
+        if self.tail.len() > 8 {
+            h2 ^= Self::mix_k2(Self::read_le(&self.tail[8..]));
+        }
+        if !self.tail.is_empty() {
+            h1 ^= Self::mix_k1(Self::read_le(&self.tail[..self.tail.len().min(8)]));
+        }
+
+        h1 ^= self.len;
+        h2 ^= self.len;
+        h1 = h1.wrapping_add(h2);
+        h2 = h2.wrapping_add(h1);
+        h1 = Self::fmix(h1);
+        h2 = Self::fmix(h2);
+        h1 = h1.wrapping_add(h2);
+        h2 = h2.wrapping_add(h1);
+
+        [h1.to_le_bytes(), h2.to_le_bytes()].concat()
+    }
+}
+
+#[cfg(test)]
+mod unit_test {
+    use data_encoding::HEXLOWER;
+    use super::*;
+
+    fn digest_hex(mut hasher: Box<dyn StreamHasher>, chunks: &[&[u8]]) -> String {
+        for chunk in chunks {
+            hasher.update(chunk);
+        }
+        HEXLOWER.encode(&hasher.finalize())
+    }
+
+    #[test]
+    fn checksum_test() {
+        let data: &[u8] = b"The quick brown fox jumps over the lazy dog";
+        assert_eq!(digest_hex(Box::new(Crc32::new()), &[data]), "414fa339");
+        assert_eq!(digest_hex(Box::new(Crc32c::new()), &[data]), "22620404");
+        assert_eq!(digest_hex(Box::new(Adler32::new()), &[data]), "5bdc0fda");
+        assert_eq!(digest_hex(Box::new(Xxh32::new()), &[b""]), "02cc5d05");
+        assert_eq!(digest_hex(Box::new(Xxh64::new()), &[b""]), "ef46db3751d8e999");
+        assert_eq!(digest_hex(Box::new(Xxh3::new(false)), &[b""]), "2d06800538d394c2");
+    }
+
+    /// MurmurHash3 分块输入的结果与一次性输入一致
+    #[test]
+    fn murmur3_test() {
+        let data: &[u8] = b"The quick brown fox jumps over the lazy dog";
+        assert_eq!(digest_hex(Box::new(Murmur3_32::new()), &[b""]), "00000000");
+        assert_eq!(digest_hex(Box::new(Murmur3_32::new()), &[data]), "2e4ff723");
+        assert_eq!(digest_hex(Box::new(Murmur3_32::new()), &[&data[..3], &data[3..5], &data[5..]]), "2e4ff723");
+        assert_eq!(digest_hex(Box::new(Murmur3_128::new()), &[data]), "6c1b07bc7bbc4be347939ac4a93c437a");
+        assert_eq!(digest_hex(Box::new(Murmur3_128::new()), &[&data[..7], &data[7..20], &data[20..]]), "6c1b07bc7bbc4be347939ac4a93c437a");
+
+        // 非零种子
+        assert_eq!(digest_hex(Box::new(Murmur3_32::with_seed(1)), &[b""]), "514e28b7");
+        assert_eq!(digest_hex(Box::new(Murmur3_32::with_seed(0xffffffff)), &[b""]), "81f16f39");
+        assert_eq!(digest_hex(Box::new(Murmur3_32::with_seed(0x9747b28c)), &[b"aaaa"]), "5a97808a");
+        assert_eq!(digest_hex(Box::new(Murmur3_32::with_seed(1234)), &[b"Hello, world!"]), "faf6cdb3");
+        for (len, expected) in [(1, "e8b5d3ba"), (2, "6113bcce"), (3, "683f1318")] {
+            assert_eq!(digest_hex(Box::new(Murmur3_32::with_seed(42)), &[&data[..len]]), expected, "{len}");
+        }
+
+        // x64_128 尾部为 1 到 15 字节的所有情况
+        let expected = [
+            "dc48e0b1dcbb8123f73522b1fca4973a", "7eb2b85fae1ce0d1ee15dc866cf829f7", "273259d34686632c776740c3fe4567f4",
+            "e8c27e06f87d694c10904504255cc17f", "d0177ac53e5ebe47f97819b153748cce", "1df260310df1046b36f7d8c25ff2b9a8",
+            "2330f50cf4a0fa6465507b609c6d20fc", "0ad1e3eefed4cc089843fcc3818d721a", "58f90944267cbee9f0c5cf09e61b6678",
+            "b02899f998558aa1f1272a7f0f491ac1", "f13d4d193de71cb2e08253a31c3f3432", "4c5358904df0352f4596852154f9eeac",
+            "0a4265ecd9b1556939d51e86d5b4f732", "7e54607dd618e0643ce7315644f433ad", "6288c89a9ef2fe6bf15c6537793b4896",
+        ];
+        for (len, expected) in (1..=15).zip(expected) {
+            assert_eq!(digest_hex(Box::new(Murmur3_128::with_seed(42)), &[&data[..len]]), expected, "{len}");
+        }
+
+        // 任意长度在任意位置分块的结果都与一次性输入一致
+        for len in 0..=data.len() {
+            let whole = digest_hex(Box::new(Murmur3_128::with_seed(7)), &[&data[..len]]);
+            let whole32 = digest_hex(Box::new(Murmur3_32::with_seed(7)), &[&data[..len]]);
+            for split in 0..=len {
+                let chunks: [&[u8]; 2] = [&data[..split], &data[split..len]];
+                assert_eq!(digest_hex(Box::new(Murmur3_128::with_seed(7)), &chunks), whole, "{len} {split}");
+                assert_eq!(digest_hex(Box::new(Murmur3_32::with_seed(7)), &chunks), whole32, "{len} {split}");
+            }
+        }
+    }
+
+    /// SMHasher 的 VerificationTest: 依次以种子 256 - i 计算 {0, 1, ..., i - 1} (i = 0..255) 的摘要,
+    /// 再以种子 0 计算全部摘要 (按参考实现的字节序) 拼接后的摘要, 取其前 4 字节作为小端序整数
+    #[test]
+    fn murmur3_verification_test() {
+        let key: Vec<u8> = (0..=255).collect();
+        let verification = |hash: &dyn Fn(&[u8], u32) -> Vec<u8>| {
+            let hashes: Vec<u8> = (0..256).flat_map(|i| hash(&key[..i], 256 - i as u32)).collect();
+            let last = hash(&hashes, 0);
+            u32::from_le_bytes([last[0], last[1], last[2], last[3]])
+        };
+        let hash32 = |data: &[u8], seed: u32| {
+            let mut hasher = Box::new(Murmur3_32::with_seed(seed));
+            hasher.update(data);
+            // 参考实现按本机 (小端) 字节序写出 32 位结果, 这里按大端序输出
+            hasher.finalize().into_iter().rev().collect()
+        };
+        let hash128 = |data: &[u8], seed: u32| {
+            let mut hasher = Box::new(Murmur3_128::with_seed(seed));
+            hasher.update(data);
+            hasher.finalize()
+        };
+
+        assert_eq!(verification(&hash32), 0xb0f57ee3);
+        assert_eq!(verification(&hash128), 0x6384ba69);
+    }
+}
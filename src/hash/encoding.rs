@@ -0,0 +1,72 @@
+use data_encoding::{BASE32, BASE64, BASE64URL_NOPAD, HEXLOWER, HEXUPPER};
+
+/// 摘要的输出编码
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DigestEncoding {
+    /// 小写十六进制 (默认)
+    Hex,
+    /// 大写十六进制
+    HexUpper,
+    /// 标准 base64 (带填充)
+    Base64,
+    /// URL 安全的 base64 (不带填充)
+    Base64Url,
+    /// RFC 4648 base32 (带填充)
+    Base32,
+    /// Subresource Integrity (`sha384-<base64>`)
+    Sri,
+}
+
+impl DigestEncoding {
+    /// 解析 `--encoding` 参数 (不区分大小写, 大写十六进制为 'hex-upper')
+    pub fn parse(encoding: &str) -> Result<DigestEncoding, String> {
+        match encoding.to_lowercase().as_str() {
+            "hex" => Ok(DigestEncoding::Hex),
+            "hex-upper" => Ok(DigestEncoding::HexUpper),
+            "base64" => Ok(DigestEncoding::Base64),
+            "base64url" => Ok(DigestEncoding::Base64Url),
+            "base32" => Ok(DigestEncoding::Base32),
+            "sri" => Ok(DigestEncoding::Sri),
+            _ => Err(format!("Invalid encoding '{encoding}' (Expect one of: hex, hex-upper, base64, base64url, base32, sri)")),
+        }
+    }
+
+    /// 是否为十六进制编码 (比对时不区分大小写)
+    pub fn is_hex(&self) -> bool {
+        matches!(self, DigestEncoding::Hex | DigestEncoding::HexUpper)
+    }
+
+    /// 编码摘要, SRI 仅支持 sha256, sha384 与 sha512
+    pub fn encode(&self, algorithm: &str, digest: &[u8]) -> Result<String, String> {
+        match self {
+            DigestEncoding::Hex => Ok(HEXLOWER.encode(digest)),
+            DigestEncoding::HexUpper => Ok(HEXUPPER.encode(digest)),
+            DigestEncoding::Base64 => Ok(BASE64.encode(digest)),
+            DigestEncoding::Base64Url => Ok(BASE64URL_NOPAD.encode(digest)),
+            DigestEncoding::Base32 => Ok(BASE32.encode(digest)),
+            DigestEncoding::Sri => match algorithm {
+                "sha256" | "sha384" | "sha512" => Ok(format!("{algorithm}-{}", BASE64.encode(digest))),
+                _ => Err(format!("SRI only supports sha256, sha384 and sha512, got '{algorithm}'")),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod unit_test {
+    use super::*;
+
+    #[test]
+    fn encode_test() {
+        let digest = [0x0a, 0xfb, 0xff];
+        assert_eq!(DigestEncoding::parse("hex").unwrap().encode("md5", &digest).unwrap(), "0afbff");
+        assert_eq!(DigestEncoding::parse("Hex").unwrap().encode("md5", &digest).unwrap(), "0afbff");
+        assert_eq!(DigestEncoding::parse("HEX-UPPER").unwrap().encode("md5", &digest).unwrap(), "0AFBFF");
+        assert_eq!(DigestEncoding::parse("Base64").unwrap().encode("md5", &digest).unwrap(), "Cvv/");
+        assert_eq!(DigestEncoding::parse("base64url").unwrap().encode("md5", &digest).unwrap(), "Cvv_");
+        assert_eq!(DigestEncoding::parse("base32").unwrap().encode("md5", &digest).unwrap(), "BL576===");
+        assert_eq!(DigestEncoding::Sri.encode("sha384", &digest).unwrap(), "sha384-Cvv/");
+        assert!(DigestEncoding::Sri.encode("md5", &digest).is_err());
+        assert!(DigestEncoding::parse("hexupper").is_err());
+    }
+}
@@ -0,0 +1,167 @@
+use std::ffi::OsStr;
+use std::fs::{self, File, Metadata};
+use std::io::{Cursor, Read};
+use std::path::Path;
+use crate::hash::hash_reader;
+use crate::hash::hasher::HasherConfig;
+
+/// Git 仓库的对象格式 (`git init --object-format`)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ObjectFormat {
+    Sha1,
+    Sha256,
+}
+
+impl ObjectFormat {
+    pub fn parse(format: &str) -> Result<ObjectFormat, String> {
+        match format.to_lowercase().as_str() {
+            "sha1" => Ok(ObjectFormat::Sha1),
+            "sha256" => Ok(ObjectFormat::Sha256),
+            _ => Err(format!("Invalid object format '{format}' (Expect one of: sha1, sha256)")),
+        }
+    }
+
+    fn algorithm(&self) -> String {
+        match self {
+            ObjectFormat::Sha1 => "sha1".to_string(),
+            ObjectFormat::Sha256 => "sha256".to_string(),
+        }
+    }
+}
+
+/// 计算对象 ID: 对 `<kind> <size>\0` 与对象内容一起计算摘要 (内容流式读取)
+fn object_id(format: ObjectFormat, kind: &str, size: u64, content: impl Read) -> Result<Vec<u8>, String> {
+    let header = Cursor::new(format!("{kind} {size}\0").into_bytes());
+    let digests = hash_reader(header.chain(content), &[format.algorithm()], &HasherConfig::default(), None)?;
+    Ok(digests.into_iter().next().map(|(_, digest)| digest).unwrap_or_default())
+}
+
+/// 计算数据的 blob ID, 与 `git hash-object --stdin` 一致
+pub fn blob_id(format: ObjectFormat, data: &[u8]) -> Result<Vec<u8>, String> {
+    object_id(format, "blob", data.len() as u64, data)
+}
+
+/// 计算文件的 blob ID, 与 `git hash-object <file>` 一致 (不做换行符转换)
+pub fn file_id(format: ObjectFormat, path: &Path) -> Result<Vec<u8>, String> {
+    let file = File::open(path).map_err(|err| format!("{err}"))?;
+    let size = file.metadata().map_err(|err| format!("{err}"))?.len();
+    object_id(format, "blob", size, file)
+}
+
+/// 计算目录的 tree ID, 与将目录中所有文件加入索引后执行 `git write-tree` 的结果一致:
+/// - 忽略 `.git` 目录及空目录 (Git 不记录空目录, 顶层目录为空时返回空 tree 的 ID)
+/// - 文件模式为 100644 或 100755 (可执行, 仅非 Windows 平台), 符号链接为 120000, 子目录为 40000
+pub fn tree_id(format: ObjectFormat, dir: &Path) -> Result<Vec<u8>, String> {
+    match subtree_id(format, dir)? {
+        Some(id) => Ok(id),
+        None => object_id(format, "tree", 0, [].as_slice()),
+    }
+}
+
+/// tree 对象中的一项
+struct TreeEntry {
+    /// 排序键, 子目录为 `<name>/`
+    key: Vec<u8>,
+    mode: &'static str,
+    name: Vec<u8>,
+    id: Vec<u8>,
+}
+
+/// 计算子目录的 tree ID, 目录中没有任何文件时返回 `None`
+fn subtree_id(format: ObjectFormat, dir: &Path) -> Result<Option<Vec<u8>>, String> {
+    let mut entries = vec![];
+
+    for item in fs::read_dir(dir).map_err(|err| format!("{}: {err}", dir.display()))? {
+        let item = item.map_err(|err| format!("{err}"))?;
+        let name = name_bytes(&item.file_name());
+        if name == b".git" {
+            continue;
+        }
+
+        let path = item.path();
+        let metadata = fs::symlink_metadata(&path).map_err(|err| format!("{}: {err}", path.display()))?;
+        if metadata.is_symlink() {
+            let target = fs::read_link(&path).map_err(|err| format!("{}: {err}", path.display()))?;
+            entries.push(TreeEntry { key: name.clone(), mode: "120000", name, id: blob_id(format, &name_bytes(target.as_os_str()))? });
+        } else if metadata.is_dir() {
+            if let Some(id) = subtree_id(format, &path)? {
+                entries.push(TreeEntry { key: [name.as_slice(), b"/"].concat(), mode: "40000", name, id });
+            }
+        } else {
+            let mode = if is_executable(&metadata) { "100755" } else { "100644" };
+            let id = file_id(format, &path).map_err(|err| format!("{}: {err}", path.display()))?;
+            entries.push(TreeEntry { key: name.clone(), mode, name, id });
+        }
+    }
+
+    if entries.is_empty() {
+        return Ok(None);
+    }
+
+    entries.sort_by(|a, b| a.key.cmp(&b.key));
+    let mut content = vec![];
+    for entry in entries {
+        content.extend_from_slice(entry.mode.as_bytes());
+        content.push(b' ');
+        content.extend_from_slice(&entry.name);
+        content.push(0);
+        content.extend_from_slice(&entry.id);
+    }
+
+    object_id(format, "tree", content.len() as u64, content.as_slice()).map(Some)
+}
+
+#[cfg(unix)]
+fn name_bytes(name: &OsStr) -> Vec<u8> {
+    use std::os::unix::ffi::OsStrExt;
+    name.as_bytes().to_vec()
+}
+
+#[cfg(not(unix))]
+fn name_bytes(name: &OsStr) -> Vec<u8> {
+    name.to_string_lossy().as_bytes().to_vec()
+}
+
+/// 与 Git 一致, 仅根据属主的可执行位判断
+#[cfg(unix)]
+fn is_executable(metadata: &Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o100 != 0
+}
+
+/// Windows 上 Git 默认不记录可执行位 (`core.filemode = false`)
+#[cfg(not(unix))]
+fn is_executable(_metadata: &Metadata) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod unit_test {
+    use super::*;
+    use data_encoding::HEXLOWER;
+
+    #[test]
+    fn blob_id_test() {
+        assert_eq!(HEXLOWER.encode(&blob_id(ObjectFormat::Sha1, b"hello\n").unwrap()), "ce013625030ba8dba906f756967f9e9ca394464a");
+        assert_eq!(
+            HEXLOWER.encode(&blob_id(ObjectFormat::Sha256, b"hello\n").unwrap()),
+            "2cf8d83d9ee29543b34a87727421fdecb7e3f3a183d337639025de576db9ebb4"
+        );
+    }
+
+    #[test]
+    fn tree_id_test() {
+        let dir = std::env::temp_dir().join("rstool_git_tree_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("a").join("empty")).unwrap();
+        assert_eq!(HEXLOWER.encode(&tree_id(ObjectFormat::Sha1, &dir).unwrap()), "4b825dc642cb6eb9a060e54bf8d69288fbee4904");
+
+        // 'a.txt' < 'a/' < 'a0', 子目录按 `<name>/` 排序
+        fs::write(dir.join("a.txt"), "hello\n").unwrap();
+        fs::write(dir.join("a").join("b"), "hello\n").unwrap();
+        fs::write(dir.join("a0"), "").unwrap();
+        assert_eq!(HEXLOWER.encode(&tree_id(ObjectFormat::Sha1, &dir).unwrap()), "0b6da3e1eff78e05f9bcfe905ed4c199862c7ae0");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,281 @@
+use blake2::{Blake2b512, Blake2s256};
+use hmac::{Mac, SimpleHmac};
+use md5::Md5;
+use ripemd::{Ripemd128, Ripemd160, Ripemd256, Ripemd320};
+use sha1::Sha1;
+use sha2::digest::core_api::BlockSizeUser;
+use sha2::digest::{Digest, DynDigest, ExtendableOutput};
+use sha2::{Sha224, Sha256, Sha384, Sha512, Sha512_224, Sha512_256};
+use sha3::{CShake128, CShake128Core, CShake256, CShake256Core, Keccak224, Keccak256, Keccak384, Keccak512, Sha3_224, Sha3_256, Sha3_384, Sha3_512, Shake128, Shake256};
+use crate::hash::checksum::{Adler32, Crc32, Crc32c, Murmur3_128, Murmur3_32, Xxh3, Xxh32, Xxh64};
+
+/// 输入不小于该大小时, BLAKE3 使用多线程计算
+const BLAKE3_RAYON_THRESHOLD: usize = 128 * 1024;
+
+/// `--algorithm all` 所包含的算法 (cSHAKE 未指定自定义字符串时与 SHAKE 相同, 因此不包含在内)
+pub const ALGORITHMS: [&str; 34] = [
+    "md5",
+    "ripemd128", "ripemd160", "ripemd256", "ripemd320",
+    "sha1",
+    "sha224", "sha256", "sha384", "sha512", "sha512_224", "sha512_256",
+    "sha3_224", "sha3_256", "sha3_384", "sha3_512", "shake128", "shake256",
+    "keccak224", "keccak256", "keccak384", "keccak512",
+    "blake2b", "blake2s", "blake3",
+    "crc32", "crc32c", "adler32",
+    "xxh32", "xxh64", "xxh3", "xxh3_128",
+    "murmur3_32", "murmur3_128",
+];
+
+// --------------------- StreamHasher ---------------------
+/// 可分块更新的摘要算法
+pub trait StreamHasher {
+    /// 使用新的数据块更新摘要
+    fn update(&mut self, data: &[u8]);
+    /// 摘要的字节长度
+    fn output_size(&self) -> usize;
+    /// 结束计算并返回摘要
+    fn finalize(self: Box<Self>) -> Vec<u8>;
+}
+
+/// `digest` 生态中实现了 `Digest` 的算法 (MD5, RIPEMD, SHA-1, SHA-2, SHA-3, BLAKE2)
+struct DigestHasher(Box<dyn DynDigest>);
+
+impl StreamHasher for DigestHasher {
+    fn update(&mut self, data: &[u8]) {
+        self.0.update(data);
+    }
+
+    fn output_size(&self) -> usize {
+        self.0.output_size()
+    }
+
+    fn finalize(self: Box<Self>) -> Vec<u8> {
+        self.0.finalize().into_vec()
+    }
+}
+
+/// 基于 `digest` 生态中的算法计算 HMAC
+struct HmacHasher<D: Digest + BlockSizeUser>(SimpleHmac<D>);
+
+impl<D: Digest + BlockSizeUser> StreamHasher for HmacHasher<D> {
+    fn update(&mut self, data: &[u8]) {
+        self.0.update(data);
+    }
+
+    fn output_size(&self) -> usize {
+        <D as Digest>::output_size()
+    }
+
+    fn finalize(self: Box<Self>) -> Vec<u8> {
+        self.0.finalize().into_bytes().to_vec()
+    }
+}
+
+/// 可扩展输出函数 (SHAKE, cSHAKE), 输出任意长度的摘要
+struct XofHasher<X: sha2::digest::Update + ExtendableOutput> {
+    state: X,
+    length: usize,
+}
+
+impl<X: sha2::digest::Update + ExtendableOutput> StreamHasher for XofHasher<X> {
+    fn update(&mut self, data: &[u8]) {
+        self.state.update(data);
+    }
+
+    fn output_size(&self) -> usize {
+        self.length
+    }
+
+    fn finalize(self: Box<Self>) -> Vec<u8> {
+        self.state.finalize_boxed(self.length).into_vec()
+    }
+}
+
+/// BLAKE3, 较大的数据块使用多线程计算
+struct Blake3Hasher(blake3::Hasher);
+
+impl StreamHasher for Blake3Hasher {
+    fn update(&mut self, data: &[u8]) {
+        if data.len() >= BLAKE3_RAYON_THRESHOLD {
+            self.0.update_rayon(data);
+        } else {
+            self.0.update(data);
+        }
+    }
+
+    fn output_size(&self) -> usize {
+        blake3::OUT_LEN
+    }
+
+    fn finalize(self: Box<Self>) -> Vec<u8> {
+        self.0.finalize().as_bytes().to_vec()
+    }
+}
+
+// --------------------- HasherConfig ---------------------
+/// 创建摘要算法实例时使用的额外参数
+#[derive(Debug, Default, Clone)]
+pub struct HasherConfig {
+    /// BLAKE3 keyed 模式使用的 32 字节密钥
+    pub blake3_key: Option<[u8; blake3::KEY_LEN]>,
+    /// BLAKE3 derive-key 模式使用的上下文字符串
+    pub blake3_context: Option<String>,
+    /// HMAC 密钥, 指定时计算 HMAC 而非普通摘要
+    pub hmac_key: Option<Vec<u8>>,
+    /// 可扩展输出函数 (SHAKE, cSHAKE) 的输出字节数, 未指定时为安全强度的两倍 (128: 32 字节, 256: 64 字节)
+    pub output_length: Option<usize>,
+    /// cSHAKE 的自定义字符串
+    pub customization: Option<String>,
+}
+
+impl HasherConfig {
+    fn blake3(&self) -> blake3::Hasher {
+        match (&self.blake3_key, &self.blake3_context) {
+            (Some(key), _) => blake3::Hasher::new_keyed(key),
+            (None, Some(context)) => blake3::Hasher::new_derive_key(context),
+            (None, None) => blake3::Hasher::new(),
+        }
+    }
+
+    fn xof<X: sha2::digest::Update + ExtendableOutput + 'static>(&self, state: X, default_length: usize) -> Box<dyn StreamHasher> {
+        Box::new(XofHasher { state, length: self.output_length.unwrap_or(default_length) })
+    }
+
+    fn customization(&self) -> &[u8] {
+        self.customization.as_deref().unwrap_or_default().as_bytes()
+    }
+}
+
+/// 创建 `digest` 生态中的算法实例, 指定了 HMAC 密钥时创建对应的 HMAC
+fn digest<D: Digest + BlockSizeUser + DynDigest + Default + Clone + 'static>(config: &HasherConfig) -> Box<dyn StreamHasher> {
+    match &config.hmac_key {
+        // SimpleHmac 接受任意长度的密钥
+        Some(key) => Box::new(HmacHasher::<D>(SimpleHmac::new_from_slice(key).unwrap())),
+        None => Box::new(DigestHasher(Box::<D>::default())),
+    }
+}
+
+/// 可用于 HMAC 的算法
+fn digest_to_hasher(algorithm: &str, config: &HasherConfig) -> Option<Box<dyn StreamHasher>> {
+    match algorithm {
+        "md5" => Some(digest::<Md5>(config)),
+        "ripemd128" => Some(digest::<Ripemd128>(config)),
+        "ripemd160" => Some(digest::<Ripemd160>(config)),
+        "ripemd256" => Some(digest::<Ripemd256>(config)),
+        "ripemd320" => Some(digest::<Ripemd320>(config)),
+        "sha1" => Some(digest::<Sha1>(config)),
+        "sha224" => Some(digest::<Sha224>(config)),
+        "sha256" => Some(digest::<Sha256>(config)),
+        "sha384" => Some(digest::<Sha384>(config)),
+        "sha512" => Some(digest::<Sha512>(config)),
+        "sha512_224" => Some(digest::<Sha512_224>(config)),
+        "sha512_256" => Some(digest::<Sha512_256>(config)),
+        "sha3_224" => Some(digest::<Sha3_224>(config)),
+        "sha3_256" => Some(digest::<Sha3_256>(config)),
+        "sha3_384" => Some(digest::<Sha3_384>(config)),
+        "sha3_512" => Some(digest::<Sha3_512>(config)),
+        "keccak224" => Some(digest::<Keccak224>(config)),
+        "keccak256" => Some(digest::<Keccak256>(config)),
+        "keccak384" => Some(digest::<Keccak384>(config)),
+        "keccak512" => Some(digest::<Keccak512>(config)),
+        "blake2b" => Some(digest::<Blake2b512>(config)),
+        "blake2s" => Some(digest::<Blake2s256>(config)),
+        _ => None
+    }
+}
+
+/// 算法是否为可扩展输出函数 (可通过 `--output-length` 指定输出长度)
+pub fn is_xof(algorithm: &str) -> bool {
+    matches!(algorithm.to_lowercase().as_str(), "shake128" | "shake256" | "cshake128" | "cshake256")
+}
+
+/// 算法是否支持 HMAC
+pub fn supports_hmac(algorithm: &str) -> bool {
+    digest_to_hasher(&algorithm.to_lowercase(), &HasherConfig::default()).is_some()
+}
+
+/// 根据算法名 (大小写不敏感) 创建摘要算法实例, 指定了 HMAC 密钥时不支持 HMAC 的算法返回 `None`
+pub fn algorithm_to_hasher(algorithm: &str, config: &HasherConfig) -> Option<Box<dyn StreamHasher>> {
+    let algorithm = algorithm.to_lowercase();
+    if let Some(hasher) = digest_to_hasher(&algorithm, config) {
+        return Some(hasher);
+    }
+    if config.hmac_key.is_some() {
+        return None;
+    }
+
+    let hasher: Box<dyn StreamHasher> = match algorithm.as_str() {
+        "blake3" => Box::new(Blake3Hasher(config.blake3())),
+        "shake128" => config.xof(Shake128::default(), 32),
+        "shake256" => config.xof(Shake256::default(), 64),
+        "cshake128" => config.xof(CShake128::from_core(CShake128Core::new(config.customization())), 32),
+        "cshake256" => config.xof(CShake256::from_core(CShake256Core::new(config.customization())), 64),
+        "crc32" => Box::new(Crc32::new()),
+        "crc32c" => Box::new(Crc32c::new()),
+        "adler32" => Box::new(Adler32::new()),
+        "xxh32" => Box::new(Xxh32::new()),
+        "xxh64" => Box::new(Xxh64::new()),
+        "xxh3" => Box::new(Xxh3::new(false)),
+        "xxh3_128" => Box::new(Xxh3::new(true)),
+        "murmur3_32" => Box::new(Murmur3_32::new()),
+        "murmur3_128" => Box::new(Murmur3_128::new()),
+        _ => return None
+    };
+
+    Some(hasher)
+}
+
+#[cfg(test)]
+mod unit_test {
+    use data_encoding::HEXLOWER;
+    use super::*;
+
+    fn digest_hex(algorithm: &str, config: &HasherConfig, data: &[u8]) -> String {
+        let mut hasher = algorithm_to_hasher(algorithm, config).unwrap();
+        hasher.update(data);
+        HEXLOWER.encode(&hasher.finalize())
+    }
+
+    #[test]
+    fn blake_test() {
+        let config = HasherConfig::default();
+        assert_eq!(digest_hex("blake2s", &config, b"abc"), "508c5e8c327c14e2e1a72ba34eeb452f37458b209ed63a294d999b4c86675982");
+        assert_eq!(digest_hex("BLAKE3", &config, b""), "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262");
+
+        // 多线程与单线程的结果一致
+        let large = vec![7u8; BLAKE3_RAYON_THRESHOLD * 3];
+        assert_eq!(digest_hex("blake3", &config, &large), blake3::hash(&large).to_hex().to_string());
+
+        let keyed = HasherConfig { blake3_key: Some([1u8; 32]), ..Default::default() };
+        assert_eq!(digest_hex("blake3", &keyed, b"abc"), blake3::keyed_hash(&[1u8; 32], b"abc").to_hex().to_string());
+    }
+
+    /// RFC 4231 test case 2
+    #[test]
+    fn hmac_test() {
+        let config = HasherConfig { hmac_key: Some(b"Jefe".to_vec()), ..Default::default() };
+        assert_eq!(
+            digest_hex("sha256", &config, b"what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert!(supports_hmac("SHA3_256"));
+        assert!(!supports_hmac("crc32"));
+        assert!(algorithm_to_hasher("crc32", &config).is_none());
+    }
+
+    #[test]
+    fn sha3_test() {
+        let config = HasherConfig::default();
+        assert_eq!(digest_hex("keccak256", &config, b""), "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470");
+        assert_eq!(digest_hex("shake128", &config, b""), "7f9c2ba4e88f827d616045507605853ed73b8093f6efbc88eb1a6eacfa66ef26");
+
+        let short = HasherConfig { output_length: Some(4), ..Default::default() };
+        assert_eq!(digest_hex("shake256", &short, b""), "46b9dd2b");
+        // 未指定自定义字符串时 cSHAKE 与 SHAKE 相同
+        assert_eq!(digest_hex("cshake256", &short, b""), "46b9dd2b");
+
+        let custom = HasherConfig { customization: Some("Email Signature".to_string()), ..Default::default() };
+        assert_ne!(digest_hex("cshake128", &custom, b""), digest_hex("shake128", &config, b""));
+        assert!(is_xof("SHAKE128") && !is_xof("sha3_256"));
+    }
+}
@@ -0,0 +1,180 @@
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// 递归收集目录下的所有文件, 返回 (相对路径, 磁盘路径) 列表, 按相对路径的字节序排序
+pub fn collect_files(root: &Path) -> Result<Vec<(String, PathBuf)>, String> {
+    let mut files = vec![];
+
+    for item in WalkDir::new(root) {
+        let item = item.map_err(|err| format!("{err}"))?;
+        if !item.file_type().is_file() {
+            continue;
+        }
+
+        // 路径层级统一成使用 '/' 分隔 (按路径组件拼接, 以保留非 Windows 平台文件名中的 '\')
+        match item.path().strip_prefix(root) {
+            Ok(relative) => {
+                let relative = relative.iter().map(|part| part.to_string_lossy()).collect::<Vec<_>>().join("/");
+                files.push((relative, item.path().to_path_buf()));
+            }
+            Err(err) => return Err(format!("{err}")),
+        }
+    }
+
+    files.sort_by(|a, b| a.0.as_bytes().cmp(b.0.as_bytes()));
+
+    Ok(files)
+}
+
+/// 展开多个来源: 文件保留命令行中的路径, 目录展开为 `<目录>/<相对路径>` (按来源的顺序);
+/// 只有一个目录时与 `collect_files` 一致, 使用相对于该目录的路径
+pub fn collect_sources(sources: &[String]) -> Result<Vec<(String, PathBuf)>, String> {
+    if let [source] = sources {
+        if Path::new(source).is_dir() {
+            return collect_files(Path::new(source));
+        }
+    }
+
+    let mut files = vec![];
+    for source in sources {
+        let path = Path::new(source);
+        if path.is_dir() {
+            let prefix = source.trim_end_matches(['/', '\\']);
+            files.extend(collect_files(path)?.into_iter().map(|(relative, disk_path)| (format!("{prefix}/{relative}"), disk_path)));
+        } else if path.is_file() {
+            files.push((source.clone(), path.to_path_buf()));
+        } else {
+            return Err(format!("{source}: No such file or directory"));
+        }
+    }
+
+    Ok(files)
+}
+
+/// 路径中含有 '\', '\n' 或 '\r' 时, 与 coreutils 相同, 对其转义, 返回 (行首前缀, 转义后的路径)
+fn escape(path: &str) -> (&'static str, String) {
+    if path.contains(['\\', '\n', '\r']) {
+        ("\\", path.replace('\\', "\\\\").replace('\n', "\\n").replace('\r', "\\r"))
+    } else {
+        ("", path.to_string())
+    }
+}
+
+/// 生成与 GNU `sha256sum`/`md5sum` 一致的清单行 (`<digest>  <path>`)
+pub fn format_line(digest: &str, path: &str) -> String {
+    let (prefix, path) = escape(path);
+    format!("{prefix}{digest}  {path}")
+}
+
+/// 算法名对应的 BSD 格式标签, 与 coreutils `--tag` 一致 (e.g. 'sha256' -> 'SHA256', 'sha3_256' -> 'SHA3-256', 'blake2b' -> 'BLAKE2b', 'ripemd160' -> 'RMD160')
+pub fn algorithm_to_tag(algorithm: &str) -> String {
+    let algorithm = algorithm.to_lowercase();
+    if let Some(bits) = algorithm.strip_prefix("ripemd") {
+        return format!("RMD{bits}");
+    }
+    match algorithm.strip_prefix("blake2") {
+        Some(variant) => format!("BLAKE2{variant}"),
+        None => algorithm.to_uppercase().replace('_', "-"),
+    }
+}
+
+/// 生成 BSD 格式的清单行 (`<TAG> (<path>) = <digest>`), 与 `sha256sum --tag` 一致
+pub fn format_tagged_line(tag: &str, digest: &str, path: &str) -> String {
+    let (prefix, path) = escape(path);
+    format!("{prefix}{tag} ({path}) = {digest}")
+}
+
+// --------------------- ManifestEntry ---------------------
+pub struct ManifestEntry {
+    /// BSD 格式中的算法标签 (e.g. 'SHA256'), GNU 格式中为 `None`
+    pub tag: Option<String>,
+    /// 期望的摘要 (小写)
+    pub digest: String,
+    /// 清单中记录的路径
+    pub path: String,
+}
+
+/// 还原 coreutils 对路径中 '\', '\n', '\r' 的转义
+fn unescape(path: &str) -> String {
+    let mut result = String::new();
+    let mut chars = path.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            result.push(ch);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some(other) => result.push(other),
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+/// 解析清单中的一行, 支持 GNU 格式 (`<digest>  <path>` 或 `<digest> *<path>`) 与 BSD 格式 (`SHA256 (path) = <digest>`)
+pub fn parse_line(line: &str) -> Option<ManifestEntry> {
+    let (escaped, line) = match line.strip_prefix('\\') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    let restore = |path: &str| if escaped { unescape(path) } else { path.to_string() };
+
+    // BSD 格式
+    if let Some((head, digest)) = line.rsplit_once(") = ") {
+        if let Some((tag, path)) = head.split_once(" (") {
+            let is_tag = !tag.is_empty() && tag.chars().all(|ch| ch.is_ascii_alphanumeric() || "-_/".contains(ch));
+            if is_tag && is_hex(digest) {
+                return Some(ManifestEntry { tag: Some(tag.to_string()), digest: digest.to_lowercase(), path: restore(path) });
+            }
+        }
+    }
+
+    // GNU 格式
+    let (digest, rest) = line.split_once(' ')?;
+    let path = rest.strip_prefix(' ').or_else(|| rest.strip_prefix('*'))?;
+    if !is_hex(digest) || path.is_empty() {
+        return None;
+    }
+
+    Some(ManifestEntry { tag: None, digest: digest.to_lowercase(), path: restore(path) })
+}
+
+fn is_hex(digest: &str) -> bool {
+    !digest.is_empty() && digest.chars().all(|ch| ch.is_ascii_hexdigit())
+}
+
+#[cfg(test)]
+mod unit_test {
+    use super::*;
+
+    #[test]
+    fn format_line_test() {
+        assert_eq!(format_line("abc", "dir/file.txt"), "abc  dir/file.txt");
+        assert_eq!(format_line("abc", "a\\b\nc"), "\\abc  a\\\\b\\nc");
+        assert_eq!(format_tagged_line("SHA256", "abc", "a\nb"), "\\SHA256 (a\\nb) = abc");
+        assert_eq!(algorithm_to_tag("sha3_256"), "SHA3-256");
+        assert_eq!(algorithm_to_tag("blake2b"), "BLAKE2b");
+        assert_eq!(algorithm_to_tag("ripemd160"), "RMD160");
+        assert_eq!(algorithm_to_tag("sha512_224"), "SHA512-224");
+    }
+
+    #[test]
+    fn parse_line_test() {
+        let gnu = parse_line("ABC123  dir/file name.txt").unwrap();
+        assert_eq!((gnu.tag, gnu.digest.as_str(), gnu.path.as_str()), (None, "abc123", "dir/file name.txt"));
+
+        let binary = parse_line("abc123 *file.bin").unwrap();
+        assert_eq!(binary.path, "file.bin");
+
+        let escaped = parse_line("\\abc  a\\\\b\\nc").unwrap();
+        assert_eq!(escaped.path, "a\\b\nc");
+
+        let bsd = parse_line("SHA256 (dir/a (1).txt) = abc123").unwrap();
+        assert_eq!((bsd.tag.as_deref(), bsd.path.as_str()), (Some("SHA256"), "dir/a (1).txt"));
+
+        assert!(parse_line("not a manifest line").is_none());
+        assert!(parse_line("").is_none());
+    }
+}
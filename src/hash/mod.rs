@@ -1,72 +1,737 @@
-use std::fs;
-use md5::Md5;
-use ripemd::{Ripemd128, Ripemd160, Ripemd256, Ripemd320};
-use sha1::Sha1;
-use sha2::{Digest, Sha224, Sha256, Sha384, Sha512, Sha512_224, Sha512_256};
-use sha3::{Sha3_224, Sha3_256, Sha3_384, Sha3_512};
-
-/// 通用hash函数
-fn hash_bytes<Hasher: Digest>(bytes: &[u8]) -> String {
-    let hex_bytes = Hasher::digest(bytes);
-    let mut result = String::new();
-    for byte in hex_bytes {
-        result += &format!("{:x}", byte);
-    }
-    result
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, ErrorKind, Read};
+use std::path::{Path, PathBuf};
+use data_encoding::{BASE64, HEXLOWER};
+use crate::archive::UnpackImpl;
+use crate::hash::git::ObjectFormat;
+use crate::hash::hasher::{algorithm_to_hasher, supports_hmac, ALGORITHMS};
+use crate::hash::utils::Progress;
+
+mod checksum;
+mod encoding;
+mod git;
+mod hasher;
+mod manifest;
+mod utils;
+
+pub(crate) use crate::hash::encoding::DigestEncoding;
+pub(crate) use crate::hash::hasher::{is_xof, HasherConfig};
+pub(crate) use crate::hash::manifest::*;
+pub(crate) use crate::hash::utils::{default_jobs, format_size, from_hex, json_string, parallel_map, strip_line_ending};
+
+/// 每次读取的块大小, 计算摘要时的内存占用与文件大小无关
+const CHUNK_SIZE: usize = 1024 * 1024;
+/// 超过该大小的文件会输出进度及吞吐量
+const PROGRESS_THRESHOLD: u64 = 64 * 1024 * 1024;
+/// 可扩展输出函数的最大输出字节数 (摘要需完整保存在内存中)
+const MAX_OUTPUT_LENGTH: usize = 1024 * 1024;
+/// 算法名及对应的摘要 (原始字节), 顺序与 `--algorithm` 中指定的顺序一致
+type Digests = Vec<(String, Vec<u8>)>;
+/// 归档中各条目的 (路径, 摘要) 列表, 以及因路径不安全而跳过的条目
+type ArchiveDigests = (Vec<(String, Digests)>, Vec<String>);
+
+/// 解析 `--algorithm` 参数, 支持以逗号分隔的多个算法 (e.g. 'md5,sha1,sha256') 以及 'all', 返回去重后的小写算法名列表
+fn parse_algorithms(algorithm: &str) -> Result<Vec<String>, String> {
+    let mut algorithms: Vec<String> = vec![];
+
+    for name in algorithm.split(',').map(|name| name.trim().to_lowercase()) {
+        let expanded = if name == "all" {
+            ALGORITHMS.iter().map(|name| name.to_string()).collect()
+        } else if algorithm_to_hasher(&name, &HasherConfig::default()).is_some() {
+            vec![name]
+        } else {
+            return Err(format!("Invalid algorithm '{name}'"));
+        };
+
+        for name in expanded {
+            if !algorithms.contains(&name) {
+                algorithms.push(name);
+            }
+        }
+    }
+
+    Ok(algorithms)
+}
+
+/// 根据 BSD 格式的算法标签推断算法 (e.g. 'SHA256' -> 'sha256', 'SHA3-256' -> 'sha3_256', 'RMD160' -> 'ripemd160')
+fn tag_to_algorithm(tag: &str) -> Option<String> {
+    let algorithm = tag.to_lowercase().replace(['-', '/'], "_");
+    let algorithm = match algorithm.strip_prefix("rmd") {
+        Some(bits) => format!("ripemd{bits}"),
+        None => algorithm,
+    };
+    algorithm_to_hasher(&algorithm, &HasherConfig::default()).map(|_| algorithm)
 }
 
-fn algorithm_to_hasher(algorithm: &str) -> Option<Box<fn(&[u8]) -> String>> {
-    match algorithm.to_lowercase().as_str() {
-        "md5" => Some(Box::new(hash_bytes::<Md5>)),
-        "ripemd128" => Some(Box::new(hash_bytes::<Ripemd128>)),
-        "ripemd160" => Some(Box::new(hash_bytes::<Ripemd160>)),
-        "ripemd256" => Some(Box::new(hash_bytes::<Ripemd256>)),
-        "ripemd320" => Some(Box::new(hash_bytes::<Ripemd320>)),
-        "sha1" => Some(Box::new(hash_bytes::<Sha1>)),
-        "sha224" => Some(Box::new(hash_bytes::<Sha224>)),
-        "sha256" => Some(Box::new(hash_bytes::<Sha256>)),
-        "sha384" => Some(Box::new(hash_bytes::<Sha384>)),
-        "sha512" => Some(Box::new(hash_bytes::<Sha512>)),
-        "sha512_224" => Some(Box::new(hash_bytes::<Sha512_224>)),
-        "sha512_256" => Some(Box::new(hash_bytes::<Sha512_256>)),
-        "sha3_224" => Some(Box::new(hash_bytes::<Sha3_224>)),
-        "sha3_256" => Some(Box::new(hash_bytes::<Sha3_256>)),
-        "sha3_384" => Some(Box::new(hash_bytes::<Sha3_384>)),
-        "sha3_512" => Some(Box::new(hash_bytes::<Sha3_512>)),
+/// 根据十六进制摘要的长度推断算法
+fn length_to_algorithm(len: usize) -> Option<String> {
+    match len {
+        32 => Some("md5".to_string()),
+        40 => Some("sha1".to_string()),
+        56 => Some("sha224".to_string()),
+        64 => Some("sha256".to_string()),
+        96 => Some("sha384".to_string()),
+        128 => Some("sha512".to_string()),
         _ => None
     }
 }
 
+/// 算法名 (大小写不敏感) 是否受支持
+pub(crate) fn supports_algorithm(algorithm: &str) -> bool {
+    algorithm_to_hasher(algorithm, &HasherConfig::default()).is_some()
+}
+
+/// 使用单个算法流式计算文件的摘要, 指定 `limit` 时只读取文件开头的 `limit` 字节 (供其他命令复用)
+pub(crate) fn digest_file(algorithm: &str, path: &Path, limit: Option<u64>) -> Result<Vec<u8>, String> {
+    let file = File::open(path).map_err(|err| format!("{err}"))?;
+    let algorithms = [algorithm.to_lowercase()];
+    let digests = match limit {
+        Some(limit) => hash_reader(file.take(limit), &algorithms, &HasherConfig::default(), None)?,
+        None => hash_reader(file, &algorithms, &HasherConfig::default(), None)?,
+    };
+
+    Ok(digests.into_iter().next().map(|(_, digest)| digest).unwrap_or_default())
+}
+
+/// 统一归档条目及清单中的路径: 使用 '/' 分隔, 去掉开头的 './'
+pub(crate) fn normalize_entry_path(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    path.trim_start_matches("./").to_string()
+}
+
+/// 以 `CHUNK_SIZE` 为单位分块读取 `reader`, 每一块都依次更新所有算法的摘要 (只读取一遍), 读取结束后返回全部摘要
+fn hash_reader(reader: impl Read, algorithms: &[String], config: &HasherConfig, progress: Option<&mut Progress>) -> Result<Digests, String> {
+    let requests: Vec<(String, HasherConfig)> = algorithms.iter().map(|algorithm| (algorithm.clone(), config.clone())).collect();
+    hash_reader_with(reader, &requests, progress)
+}
+
+/// 与 `hash_reader` 相同, 但每个算法使用各自的配置 (校验清单时同一文件可能对应不同输出长度的可扩展输出函数)
+fn hash_reader_with(mut reader: impl Read, requests: &[(String, HasherConfig)], mut progress: Option<&mut Progress>) -> Result<Digests, String> {
+    let mut hashers = vec![];
+    for (algorithm, config) in requests {
+        match algorithm_to_hasher(algorithm, config) {
+            Some(hasher) => hashers.push(hasher),
+            None => return Err(format!("Invalid algorithm '{algorithm}'")),
+        }
+    }
+    let mut buffer = vec![0u8; CHUNK_SIZE];
+
+    loop {
+        let len = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(len) => len,
+            Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+            Err(err) => return Err(format!("{err}")),
+        };
+
+        for hasher in hashers.iter_mut() {
+            hasher.update(&buffer[..len]);
+        }
+        if let Some(progress) = progress.as_mut() {
+            progress.advance(len);
+        }
+    }
+
+    Ok(requests.iter().map(|(algorithm, _)| algorithm.clone()).zip(hashers.into_iter().map(|hasher| hasher.finalize())).collect())
+}
+
+/// 按照 `encoding` 编码所有摘要
+fn encode_digests(digests: &Digests, encoding: DigestEncoding) -> Result<Vec<(String, String)>, String> {
+    digests.iter().map(|(name, digest)| encoding.encode(name, digest).map(|encoded| (name.clone(), encoded))).collect()
+}
+
+/// 格式化单个来源的计算结果: 单个算法时仅输出摘要, 多个算法时输出表格, 或者输出 JSON 对象
+fn format_digests(digests: &Digests, encoding: DigestEncoding, json: bool) -> Result<String, String> {
+    let encoded = encode_digests(digests, encoding)?;
+
+    if json {
+        let fields: Vec<String> = encoded.iter().map(|(name, digest)| format!("{}: {}", json_string(name), json_string(digest))).collect();
+        Ok(format!("{{{}}}", fields.join(", ")))
+    } else if encoded.len() == 1 {
+        Ok(encoded[0].1.clone())
+    } else {
+        let width = encoded.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+        let rows: Vec<String> = encoded.iter().map(|(name, digest)| format!("{name:<width$}  {digest}")).collect();
+        Ok(rows.join("\n"))
+    }
+}
+
+/// Arguments of `Commands::Hash`
+pub struct HashOptions {
+    pub sources: Vec<String>,
+    pub filemode: bool,
+    pub algorithm: String,
+    pub output: Option<String>,
+    pub check: Option<String>,
+    pub json: bool,
+    pub blake3_key: Option<String>,
+    pub blake3_context: Option<String>,
+    pub hmac_key: Option<String>,
+    pub hmac_key_file: Option<String>,
+    pub hmac_key_encoding: String,
+    pub expect: Option<String>,
+    pub encoding: String,
+    pub input_encoding: String,
+    pub jobs: Option<usize>,
+    pub output_length: Option<usize>,
+    pub customization: Option<String>,
+    pub git: Option<String>,
+    pub archive: bool,
+}
+
 /// Supported algorithms (case insensitive):
 /// - md5 (MD5) -- default
 /// - ripemd (Ripemd128; Ripemd160; Ripemd256; Ripemd320)
 /// - sha1 (SHA1)
 /// - sha2 (SHA224; SHA256; SHA384; SHA512; SHA512_224; SHA512_256)
 /// - sha3 (SHA3_224; SHA3_256; SHA3_384; SHA3_512)
+/// - blake (BLAKE2b; BLAKE2s; BLAKE3)
+/// - checksum (CRC32; CRC32C; Adler32; XXH32; XXH64; XXH3; XXH3_128; Murmur3_32; Murmur3_128)
 pub struct HashImpl {}
 
 impl HashImpl {
-    /// 使用指定算法计算目标的摘要
-    fn hash(algorithm: String, bytes: &[u8]) -> Result<String, String> {
-        match algorithm_to_hasher(&algorithm) {
-            Some(hasher) => Ok(hasher(bytes)),
-            None => Err(format!("Invalid algorithm '{algorithm}'")),
+    /// 使用指定算法计算字符串的摘要, 字符串按照 `input_encoding` 解码为字节
+    fn hash_string(algorithms: &[String], config: &HasherConfig, source: String, input_encoding: &str) -> Result<Digests, String> {
+        let data = HashImpl::decode_input(source.into_bytes(), input_encoding)?;
+        hash_reader(data.as_slice(), algorithms, config, None)
+    }
+
+    /// 使用指定算法计算标准输入的摘要, 'utf8' 时按原始字节流式读取, 其余编码需读取全部输入后解码
+    fn hash_stdin(algorithms: &[String], config: &HasherConfig, input_encoding: &str) -> Result<Digests, String> {
+        let mut stdin = io::stdin().lock();
+        if input_encoding.eq_ignore_ascii_case("utf8") {
+            return hash_reader(stdin, algorithms, config, None);
         }
+
+        let mut raw = vec![];
+        stdin.read_to_end(&mut raw).map_err(|err| format!("{err}"))?;
+        let data = HashImpl::decode_input(raw, input_encoding)?;
+        hash_reader(data.as_slice(), algorithms, config, None)
     }
 
-    /// 处理 Command::Hash 子命令
-    pub fn handle(source: String, filemode: bool, algorithm: String) {
-        println!("[Commands::Hash] source = '{source}', filemode = '{filemode}', algorithm = '{algorithm}'");
+    /// 使用指定算法流式计算文件的摘要 (`show_progress` 时大文件会输出进度及吞吐量)
+    fn hash_file(algorithms: &[String], config: &HasherConfig, source: &Path, show_progress: bool) -> Result<Digests, String> {
+        let file = File::open(source).map_err(|err| format!("{err}"))?;
+        let size = file.metadata().map_err(|err| format!("{err}"))?.len();
+
+        if show_progress && size > PROGRESS_THRESHOLD {
+            let mut progress = Progress::new(size);
+            let digests = hash_reader(file, algorithms, config, Some(&mut progress))?;
+            progress.finish();
+            Ok(digests)
+        } else {
+            hash_reader(file, algorithms, config, None)
+        }
+    }
 
-        let source_bytes: Result<Vec<u8>, String> = if !filemode { Ok(source.into_bytes()) } else {
-            fs::read(source).map_err(|err| format!("{err}"))
+    /// 使用 `jobs` 个线程并行计算多个文件的摘要, 返回 (路径, 摘要) 列表, 顺序与 `files` 一致
+    /// 并行时多个文件的进度会相互覆盖, 因此仅在单线程时输出进度
+    pub(crate) fn hash_files(algorithms: &[String], config: &HasherConfig, files: Vec<(String, PathBuf)>, jobs: usize) -> Result<Vec<(String, Digests)>, String> {
+        let digests = parallel_map(&files, jobs, |(path, disk_path)| {
+            HashImpl::hash_file(algorithms, config, disk_path, jobs == 1).map_err(|err| format!("{path}: {err}"))
+        })?;
+
+        Ok(files.into_iter().map(|(path, _)| path).zip(digests).collect())
+    }
+
+    /// 流式读取归档中的文件并逐个计算摘要 (内存占用与归档大小无关), `requests` 返回条目需要计算的 (算法, 配置), 返回 `None` 的条目会被忽略
+    /// 返回 (条目路径, 摘要) 列表 (按路径排序) 及因路径不安全而跳过的条目
+    fn hash_archive<F>(source: &str, requests: F) -> Result<ArchiveDigests, String>
+    where
+        F: Fn(&str) -> Option<Vec<(String, HasherConfig)>>,
+    {
+        let mut results = vec![];
+        let skipped = UnpackImpl::read_files(source, &mut |path, reader| {
+            let path = normalize_entry_path(path);
+            if let Some(requests) = requests(&path) {
+                let digests = hash_reader_with(reader, &requests, None).map_err(|err| format!("{path}: {err}"))?;
+                results.push((path, digests));
+            }
+            Ok(())
+        })?;
+        results.sort_by(|a, b| a.0.as_bytes().cmp(b.0.as_bytes()));
+
+        Ok((results, skipped))
+    }
+
+    /// 输出因路径不安全 (绝对路径或含有 '..') 而跳过的归档条目
+    fn report_skipped(skipped: &[String], bare: bool) {
+        for name in skipped {
+            let warning = format!("WARNING: Skipped '{name}', the path in the archive is unsafe");
+            if bare { eprintln!("{warning}") } else { println!("{warning}") }
+        }
+    }
+
+    /// 生成目录清单:
+    /// - 单个算法: 与 `sha256sum`/`md5sum` 格式一致 (`<digest>  <path>`)
+    /// - 多个算法: BSD 格式 (`SHA256 (path) = <digest>`), 每个文件每个算法一行
+    /// - JSON: `{"path": {"algorithm": "digest"}}`
+    fn format_manifest(results: Vec<(String, Digests)>, encoding: DigestEncoding, json: bool) -> Result<Vec<String>, String> {
+        if json {
+            let mut fields = vec![];
+            for (path, digests) in &results {
+                fields.push(format!("  {}: {}", json_string(path), format_digests(digests, encoding, true)?));
+            }
+            return Ok(vec![format!("{{\n{}\n}}", fields.join(",\n"))]);
+        }
+
+        let mut lines = vec![];
+        for (path, digests) in results {
+            let encoded = encode_digests(&digests, encoding)?;
+            if encoded.len() == 1 {
+                lines.push(format_line(&encoded[0].1, &path));
+            } else {
+                for (name, digest) in encoded {
+                    lines.push(format_tagged_line(&algorithm_to_tag(&name), &digest, &path));
+                }
+            }
+        }
+        Ok(lines)
+    }
+
+    /// 文本清单只能使用十六进制编码, 否则无法被 `--check` (及 `sha256sum -c`) 读取; JSON 清单不受限制
+    fn check_manifest_encoding(encoding: DigestEncoding, json: bool) -> Result<(), String> {
+        if json || encoding.is_hex() {
+            Ok(())
+        } else {
+            Err("A manifest can only hold hex digests, so it can be read back by '--check' (use '--json' for other encodings)".to_string())
+        }
+    }
+
+    /// 输出目录清单, 指定了 `output` 时写入文件并返回提示信息, 否则只打印清单本身 (可直接重定向为 `sha256sum -c` 可用的文件)
+    fn emit_manifest(lines: Vec<String>, output: Option<String>) -> Result<Option<String>, String> {
+        match output {
+            Some(output) => {
+                let content: String = lines.into_iter().map(|line| line + "\n").collect();
+                fs::write(&output, content).map_err(|err| format!("{err}"))?;
+                Ok(Some(format!("Manifest written to '{output}'")))
+            }
+            None => {
+                for line in lines {
+                    println!("{line}");
+                }
+                Ok(None)
+            }
+        }
+    }
+
+    /// 输出错误, `bare` (stdout 只输出清单) 时输出到 stderr 并以非零状态码退出
+    fn fail(err: &str, bare: bool) {
+        if bare {
+            eprintln!("Error: {err}");
+            std::process::exit(1);
+        }
+        println!("Error: {err}");
+    }
+
+    /// 输出清单的生成结果
+    fn finish_manifest(result: Result<Option<String>, String>, bare: bool) {
+        match result {
+            Ok(Some(message)) => println!("Ok: {message}"),
+            Ok(None) => {}
+            Err(err) => {
+                HashImpl::fail(&err, bare);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    /// 清单中某一行所使用的算法及配置 (可扩展输出函数的输出长度与清单中的摘要一致), 无法确定算法时返回 `None`
+    fn line_request(entry: &ManifestEntry, algorithm: &str, config: &HasherConfig) -> Option<(String, HasherConfig)> {
+        let algorithm = HashImpl::resolve_algorithm(entry, algorithm)?;
+        let config = match is_xof(&algorithm) {
+            true => HasherConfig { output_length: Some(entry.digest.len() / 2), ..config.clone() },
+            false => config.clone(),
         };
+        Some((algorithm, config))
+    }
+
+    /// 读取清单, 返回每个路径 (统一格式后) 需要计算的 (算法, 配置), 供校验归档时只读取一遍归档
+    fn manifest_requests(manifest: &str, algorithm: &str, config: &HasherConfig) -> Result<HashMap<String, Vec<(String, HasherConfig)>>, String> {
+        let content = fs::read_to_string(manifest).map_err(|err| format!("{err}"))?;
+        let mut requests: HashMap<String, Vec<(String, HasherConfig)>> = HashMap::new();
+
+        for entry in content.lines().filter(|line| !line.starts_with('#')).filter_map(parse_line) {
+            if let Some(request) = HashImpl::line_request(&entry, algorithm, config) {
+                let list = requests.entry(normalize_entry_path(&entry.path)).or_default();
+                if !list.iter().any(|(name, config)| *name == request.0 && config.output_length == request.1.output_length) {
+                    list.push(request);
+                }
+            }
+        }
+
+        Ok(requests)
+    }
+
+    /// 确定清单中某一行所使用的算法:
+    /// - BSD 格式: 由算法标签决定
+    /// - GNU 格式: 若 `algorithm` 的摘要长度与该行一致则使用 `algorithm`, 否则根据摘要长度推断
+    pub(crate) fn resolve_algorithm(entry: &ManifestEntry, algorithm: &str) -> Option<String> {
+        match &entry.tag {
+            Some(tag) => tag_to_algorithm(tag),
+            None => match algorithm_to_hasher(algorithm, &HasherConfig::default()) {
+                Some(hasher) if is_xof(algorithm) || hasher.output_size() * 2 == entry.digest.len() => Some(algorithm.to_lowercase()),
+                _ => length_to_algorithm(entry.digest.len()),
+            }
+        }
+    }
+
+    /// 校验清单中列出的所有文件, 逐行输出 OK/FAILED/MISSING, 返回是否全部通过
+    ///
+    /// `hash_entry` 根据清单中的路径计算摘要, 路径不存在时返回 `None`
+    fn check<F>(manifest: &str, algorithm: &str, config: &HasherConfig, hash_entry: F) -> Result<bool, String>
+    where
+        F: Fn(&str, &[String], &HasherConfig) -> Option<Result<Digests, String>>,
+    {
+        let content = fs::read_to_string(manifest).map_err(|err| format!("{err}"))?;
+
+        let (mut passed, mut failed, mut missing, mut malformed) = (0, 0, 0, 0);
+        for line in content.lines() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let entry = match parse_line(line) {
+                Some(entry) => entry,
+                None => {
+                    malformed += 1;
+                    continue;
+                }
+            };
+
+            let digests = match HashImpl::line_request(&entry, algorithm, config) {
+                Some((algorithm, config)) => hash_entry(&entry.path, &[algorithm], &config),
+                None => Some(Err("Unknown algorithm".to_string())),
+            };
+            let matched = match digests {
+                Some(digests) => digests.map(|digests| HEXLOWER.encode(&digests[0].1) == entry.digest),
+                None => {
+                    missing += 1;
+                    println!("{}: MISSING", entry.path);
+                    continue;
+                }
+            };
+            match matched {
+                Ok(true) => {
+                    passed += 1;
+                    println!("{}: OK", entry.path);
+                }
+                Ok(false) => {
+                    failed += 1;
+                    println!("{}: FAILED", entry.path);
+                }
+                Err(err) => {
+                    failed += 1;
+                    println!("{}: FAILED ({err})", entry.path);
+                }
+            }
+        }
+
+        if malformed > 0 {
+            println!("WARNING: {malformed} line(s) are improperly formatted");
+        }
+        if passed + failed + missing == 0 {
+            return Err(format!("No properly formatted checksum lines found in '{manifest}'"));
+        }
+        println!("Summary: {passed} OK, {failed} FAILED, {missing} MISSING");
+
+        Ok(failed == 0 && missing == 0)
+    }
+
+    /// 按照 `encoding` (utf8, hex, base64) 解码 HMAC 密钥
+    pub(crate) fn decode_key(raw: Vec<u8>, encoding: &str) -> Result<Vec<u8>, String> {
+        let text = || String::from_utf8(raw.clone()).map(|text| text.trim().to_string()).map_err(|err| format!("{err}"));
 
-        match source_bytes {
-            Ok(bytes) => match HashImpl::hash(algorithm, &bytes) {
-                Ok(result) => println!("Ok: {result}"),
-                Err(err) => println!("Error: {err}"),
+        match encoding.to_lowercase().as_str() {
+            "utf8" => Ok(raw),
+            "hex" => from_hex(&text()?).ok_or("Invalid hex key".to_string()),
+            "base64" => BASE64.decode(text()?.as_bytes()).map_err(|err| format!("Invalid base64 key ({err})")),
+            _ => Err(format!("Invalid key encoding '{encoding}' (Expect one of: utf8, hex, base64)")),
+        }
+    }
+
+    /// 按照 `--input-encoding` 解码输入 ('hex' 与 'base64' 忽略其中的空白字符, 'utf16le' 将文本转为 UTF-16LE 字节)
+    fn decode_input(raw: Vec<u8>, encoding: &str) -> Result<Vec<u8>, String> {
+        let text = || String::from_utf8(raw.clone()).map_err(|err| format!("{err}"));
+        let compact = || text().map(|text| text.split_whitespace().collect::<String>());
+
+        match encoding.to_lowercase().as_str() {
+            "utf8" => Ok(raw),
+            "hex" => from_hex(&compact()?).ok_or("Invalid hex input".to_string()),
+            "base64" => BASE64.decode(compact()?.as_bytes()).map_err(|err| format!("Invalid base64 input ({err})")),
+            "utf16le" => Ok(text()?.encode_utf16().flat_map(|unit| unit.to_le_bytes()).collect()),
+            _ => Err(format!("Invalid input encoding '{encoding}' (Expect one of: utf8, hex, base64, utf16le)")),
+        }
+    }
+
+    /// 计算 Git 对象 ID: 文件为 blob ID, 目录为 tree ID, 字符串及标准输入 (`filemode` 时的 '-') 为其内容的 blob ID
+    fn git_id(format: ObjectFormat, source: &str, filemode: bool, input_encoding: &str) -> Result<String, String> {
+        let id = if !filemode {
+            git::blob_id(format, &HashImpl::decode_input(source.as_bytes().to_vec(), input_encoding)?)?
+        } else if source == "-" {
+            let mut raw = vec![];
+            io::stdin().lock().read_to_end(&mut raw).map_err(|err| format!("{err}"))?;
+            git::blob_id(format, &HashImpl::decode_input(raw, input_encoding)?)?
+        } else if Path::new(source).is_dir() {
+            git::tree_id(format, Path::new(source))?
+        } else {
+            git::file_id(format, Path::new(source))?
+        };
+
+        Ok(HEXLOWER.encode(&id))
+    }
+
+    /// 比对摘要与期望值 (按照 `encoding` 编码后比较, 十六进制不区分大小写), 期望值可以带有算法前缀 (e.g. 'sha256=...', 与 GitHub webhook 的签名头一致)
+    fn verify(digests: &Digests, expect: &str, encoding: DigestEncoding) -> Result<(), String> {
+        if digests.len() != 1 {
+            return Err("'--expect' can only be used with a single algorithm".to_string());
+        }
+
+        let (name, digest) = &encode_digests(digests, encoding)?[0];
+        let expect = expect.trim();
+        let expect = match expect.split_once('=') {
+            Some((prefix, value)) if prefix.eq_ignore_ascii_case(name) && encoding != DigestEncoding::Sri => value,
+            _ => expect,
+        };
+
+        let matched = if encoding.is_hex() { expect.eq_ignore_ascii_case(digest) } else { expect == digest };
+        if matched {
+            Ok(())
+        } else {
+            Err(format!("Digest mismatch, expect '{expect}', got '{digest}'"))
+        }
+    }
+
+    /// 解析 BLAKE3 keyed/derive-key 模式以及 HMAC 的参数
+    fn parse_config(options: &HashOptions) -> Result<HasherConfig, String> {
+        let blake3_key = match &options.blake3_key {
+            Some(key) => match from_hex(key).map(<[u8; 32]>::try_from) {
+                Some(Ok(key)) => Some(key),
+                _ => return Err("BLAKE3 key must be 32 bytes (64 hex digits)".to_string()),
             },
+            None => None,
+        };
+        if blake3_key.is_some() && options.blake3_context.is_some() {
+            return Err("'--blake3-key' and '--blake3-context' can not be used together".to_string());
+        }
+
+        let hmac_key = match (&options.hmac_key, &options.hmac_key_file) {
+            (Some(_), Some(_)) => return Err("'--hmac-key' and '--hmac-key-file' can not be used together".to_string()),
+            (Some(key), None) => Some(HashImpl::decode_key(key.clone().into_bytes(), &options.hmac_key_encoding)?),
+            (None, Some(path)) => {
+                let raw = fs::read(path).map_err(|err| format!("{err}"))?;
+                Some(HashImpl::decode_key(strip_line_ending(raw), &options.hmac_key_encoding)?)
+            }
+            (None, None) => None,
+        };
+
+        match options.output_length {
+            Some(0) => return Err("'--output-length' must be greater than 0".to_string()),
+            Some(length) if length > MAX_OUTPUT_LENGTH => {
+                return Err(format!("'--output-length' must not exceed {MAX_OUTPUT_LENGTH} bytes (1 MiB), got {length}"));
+            }
+            _ => {}
+        }
+
+        Ok(HasherConfig {
+            blake3_key,
+            blake3_context: options.blake3_context.clone(),
+            hmac_key,
+            output_length: options.output_length,
+            customization: options.customization.clone(),
+        })
+    }
+
+    /// 处理 Command::Hash 子命令
+    pub fn handle(options: HashOptions) {
+        let config = match HashImpl::parse_config(&options) {
+            Ok(config) => config,
+            Err(err) => return println!("Error: {err}"),
+        };
+        let encoding = match DigestEncoding::parse(&options.encoding) {
+            Ok(encoding) => encoding,
+            Err(err) => return println!("Error: {err}"),
+        };
+        let HashOptions { sources, filemode, algorithm, output, check, json, expect, input_encoding, jobs, git, archive, .. } = options;
+
+        if archive && git.is_some() {
+            return println!("Error: '--archive' can not be used with '--git'");
+        }
+        if archive && sources.len() != 1 {
+            return println!("Error: '--archive' expects exactly one archive");
+        }
+
+        if let Some(manifest) = check {
+            if sources.len() > 1 {
+                return println!("Error: '--check' accepts at most one base directory");
+            }
+            let base = sources.into_iter().next();
+            println!("[Commands::Hash] check = '{manifest}', base = '{}', algorithm = '{algorithm}', archive = '{archive}'", base.clone().unwrap_or("default".to_string()));
+
+            let result = if archive {
+                // 校验归档中的条目: 先读取一遍归档计算清单中列出的条目的摘要, 再逐行比对
+                HashImpl::manifest_requests(&manifest, &algorithm, &config).and_then(|requests| {
+                    let (results, skipped) = HashImpl::hash_archive(&base.unwrap_or_default(), |path| requests.get(path).cloned())?;
+                    HashImpl::report_skipped(&skipped, false);
+
+                    // 摘要的顺序与 `requests` 中该路径的 (算法, 配置) 一致
+                    let results: HashMap<String, Digests> = results.into_iter().collect();
+                    HashImpl::check(&manifest, &algorithm, &config, |path, algorithms, config| {
+                        let path = normalize_entry_path(path);
+                        results.get(&path).map(|digests| {
+                            requests[&path].iter()
+                                .position(|(name, request)| *name == algorithms[0] && request.output_length == config.output_length)
+                                .map(|index| vec![digests[index].clone()])
+                                .ok_or("Unknown algorithm".to_string())
+                        })
+                    })
+                })
+            } else {
+                // 清单中的相对路径基于 `base` 解析, 未指定时基于清单文件所在的目录
+                let base = match base {
+                    Some(base) => PathBuf::from(base),
+                    None => Path::new(&manifest).parent().map_or(PathBuf::new(), |parent| parent.to_path_buf()),
+                };
+                HashImpl::check(&manifest, &algorithm, &config, |path, algorithms, config| {
+                    let disk_path = base.join(path);
+                    disk_path.is_file().then(|| HashImpl::hash_file(algorithms, config, &disk_path, true))
+                })
+            };
+
+            match result {
+                Ok(true) => println!("Ok"),
+                Ok(false) => {
+                    println!("Error: Checksum verification failed");
+                    std::process::exit(1);
+                }
+                Err(err) => {
+                    println!("Error: {err}");
+                    std::process::exit(1);
+                }
+            }
+            return;
+        }
+
+        let source = sources.join(", ");
+
+        if let Some(format) = git {
+            println!("[Commands::Hash] source = '{source}', filemode = '{filemode}', git = '{format}'");
+            let format = match ObjectFormat::parse(&format) {
+                Ok(format) => format,
+                Err(err) => return println!("Error: {err}"),
+            };
+            if sources.len() > 1 && !filemode {
+                return println!("Error: Multiple sources can only be hashed with 'filemode'");
+            }
+
+            let mut lines = vec![];
+            for source in &sources {
+                match HashImpl::git_id(format, source, filemode, &input_encoding) {
+                    Ok(id) if sources.len() == 1 => lines.push(id),
+                    Ok(id) => lines.push(format_line(&id, source)),
+                    Err(err) => return println!("Error: {err}"),
+                }
+            }
+
+            return match &expect {
+                Some(expect) if lines.len() == 1 && lines[0].eq_ignore_ascii_case(expect.trim()) => println!("Ok: Digest matches"),
+                Some(expect) if lines.len() == 1 => {
+                    println!("Error: Digest mismatch, expect '{}', got '{}'", expect.trim(), lines[0]);
+                    std::process::exit(1);
+                }
+                Some(_) => println!("Error: '--expect' can only be used with a single source"),
+                None if lines.len() == 1 => println!("Ok: {}", lines[0]),
+                None => println!("Ok:\n{}", lines.join("\n")),
+            };
+        }
+
+        // filemode 时 '-' 表示从标准输入读取, 否则按普通字符串计算
+        let stdin = filemode && source == "-";
+        // 多个来源, 目录或归档时生成清单
+        let source_path = Path::new(&source);
+        let manifest = archive || (filemode && !stdin && (sources.len() > 1 || source_path.is_dir()));
+        // 清单直接输出到 stdout 或输出 JSON 时不输出其他内容
+        let bare = output.is_none() && (manifest || json) && expect.is_none();
+
+        if !bare {
+            println!("[Commands::Hash] source = '{source}', filemode = '{filemode}', algorithm = '{algorithm}', hmac = '{hmac}'", hmac = config.hmac_key.is_some());
+        }
+
+        let algorithms = match parse_algorithms(&algorithm) {
+            Ok(algorithms) => algorithms,
+            Err(err) => return HashImpl::fail(&err, bare),
+        };
+        if config.hmac_key.is_some() {
+            if let Some(name) = algorithms.iter().find(|name| !supports_hmac(name)) {
+                return HashImpl::fail(&format!("HMAC is not supported for '{name}'"), bare);
+            }
+        }
+        if config.output_length.is_some() && !algorithms.iter().any(|name| is_xof(name)) {
+            return HashImpl::fail("'--output-length' only applies to shake128, shake256, cshake128 and cshake256", bare);
+        }
+        if config.customization.is_some() && !algorithms.iter().any(|name| name.starts_with("cshake")) {
+            return HashImpl::fail("'--customization' only applies to cshake128 and cshake256", bare);
+        }
+        if manifest {
+            if let Err(err) = HashImpl::check_manifest_encoding(encoding, json) {
+                return HashImpl::fail(&err, bare);
+            }
+        }
+
+        let jobs = jobs.filter(|jobs| *jobs > 0).unwrap_or_else(default_jobs);
+        if archive {
+            let requests: Vec<(String, HasherConfig)> = algorithms.iter().map(|algorithm| (algorithm.clone(), config.clone())).collect();
+            let result = HashImpl::hash_archive(&source, |_| Some(requests.clone()))
+                .and_then(|(results, skipped)| {
+                    HashImpl::report_skipped(&skipped, bare);
+                    HashImpl::format_manifest(results, encoding, json)
+                })
+                .and_then(|lines| HashImpl::emit_manifest(lines, output));
+            return HashImpl::finish_manifest(result, bare);
+        }
+
+        if sources.len() > 1 && !filemode {
+            return println!("Error: Multiple sources can only be hashed with 'filemode'");
+        }
+        if filemode && !stdin && !input_encoding.eq_ignore_ascii_case("utf8") {
+            return HashImpl::fail("'--input-encoding' only applies to string sources and stdin", bare);
+        }
+
+        // 多个来源或目录时并行计算
+        if manifest {
+            let result = collect_sources(&sources)
+                .and_then(|files| HashImpl::hash_files(&algorithms, &config, files, jobs))
+                .and_then(|results| HashImpl::format_manifest(results, encoding, json))
+                .and_then(|lines| HashImpl::emit_manifest(lines, output));
+            return HashImpl::finish_manifest(result, bare);
+        }
+
+        let digests = if stdin {
+            HashImpl::hash_stdin(&algorithms, &config, &input_encoding)
+        } else if filemode {
+            HashImpl::hash_file(&algorithms, &config, source_path, true)
+        } else {
+            HashImpl::hash_string(&algorithms, &config, source.clone(), &input_encoding)
+        };
+
+        if let (Ok(digests), Some(expect)) = (&digests, &expect) {
+            match HashImpl::verify(digests, expect, encoding) {
+                Ok(_) => println!("Ok: Digest matches"),
+                Err(err) => {
+                    println!("Error: {err}");
+                    std::process::exit(1);
+                }
+            }
+            return;
+        }
+
+        match digests.and_then(|digests| format_digests(&digests, encoding, json)) {
+            Ok(result) if bare => println!("{result}"),
+            Err(err) if bare => HashImpl::fail(&err, bare),
+            // 多个算法的表格从下一行开始输出
+            Ok(result) if result.contains('\n') => println!("Ok:\n{result}"),
+            Ok(result) => println!("Ok: {result}"),
             Err(err) => println!("Error: {err}"),
         }
     }
@@ -74,6 +739,9 @@ impl HashImpl {
 
 #[cfg(test)]
 mod unit_test {
+    use std::io::Cursor;
+    use super::*;
+
     #[test]
     fn hash_test() {
         // HashImpl::handle("hello world".to_string(), false, "md5".to_string());
@@ -83,4 +751,93 @@ mod unit_test {
 
         // HashImpl::handle("./aho_corasick-6ed754f677c9af28.d".to_string(), true, "sha256".to_string());
     }
-}
\ No newline at end of file
+
+    /// 分块读取的结果应与一次性计算的结果一致
+    #[test]
+    fn hash_reader_test() {
+        let source: Vec<u8> = (0..CHUNK_SIZE * 2 + 17).map(|i| (i % 251) as u8).collect();
+
+        let config = HasherConfig::default();
+        let mut hasher = algorithm_to_hasher("sha256", &config).unwrap();
+        hasher.update(&source);
+        let expected = hasher.finalize();
+
+        let streamed = hash_reader(Cursor::new(source), &["sha256".to_string()], &config, None).unwrap();
+        assert_eq!(streamed[0].1, expected);
+    }
+
+    /// 十六进制输出应保留前导零
+    #[test]
+    fn hex_test() {
+        assert_eq!(format_digests(&vec![("md5".to_string(), vec![0x0a, 0x00, 0xff])], DigestEncoding::Hex, false).unwrap(), "0a00ff");
+        assert_eq!(
+            HEXLOWER.encode(&HashImpl::hash_string(&["sha256".to_string()], &HasherConfig::default(), "hello world".to_string(), "utf8").unwrap()[0].1),
+            "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9"
+        );
+    }
+
+    #[test]
+    fn resolve_algorithm_test() {
+        let bsd = parse_line("SHA3-256 (a.txt) = 00").unwrap();
+        assert_eq!(HashImpl::resolve_algorithm(&bsd, "md5").as_deref(), Some("sha3_256"));
+
+        let gnu = parse_line(&format!("{}  a.txt", "0".repeat(64))).unwrap();
+        assert_eq!(HashImpl::resolve_algorithm(&gnu, "md5").as_deref(), Some("sha256"));
+        assert_eq!(HashImpl::resolve_algorithm(&gnu, "SHA3_256").as_deref(), Some("sha3_256"));
+
+        // BSD 标签与算法名可以互相转换
+        for algorithm in ALGORITHMS {
+            assert_eq!(tag_to_algorithm(&algorithm_to_tag(algorithm)).as_deref(), Some(algorithm));
+        }
+    }
+
+    /// 多个算法共用一次读取, 结果顺序与指定顺序一致
+    #[test]
+    fn multiple_algorithms_test() {
+        let algorithms = parse_algorithms("SHA1, md5,sha1").unwrap();
+        assert_eq!(algorithms, vec!["sha1", "md5"]);
+        assert_eq!(parse_algorithms("all").unwrap().len(), ALGORITHMS.len());
+        assert!(parse_algorithms("md5,sha").is_err());
+
+        let digests = HashImpl::hash_string(&algorithms, &HasherConfig::default(), "hello world".to_string(), "utf8").unwrap();
+        assert_eq!(
+            encode_digests(&digests, DigestEncoding::Hex).unwrap(),
+            vec![
+                ("sha1".to_string(), "2aae6c35c94fcfb415dbe95f408b9ce91ee846ed".to_string()),
+                ("md5".to_string(), "5eb63bbbe01eeed093cb22bb8f5acdc3".to_string()),
+            ]
+        );
+        assert_eq!(
+            format_digests(&digests, DigestEncoding::Hex, true).unwrap(),
+            r#"{"sha1": "2aae6c35c94fcfb415dbe95f408b9ce91ee846ed", "md5": "5eb63bbbe01eeed093cb22bb8f5acdc3"}"#
+        );
+
+        // 文本清单只能是十六进制, 才能被 `--check` 读回
+        let lines = HashImpl::format_manifest(vec![("a.txt".to_string(), digests)], DigestEncoding::HexUpper, false).unwrap();
+        assert!(lines.iter().all(|line| parse_line(line).is_some()));
+        assert!(HashImpl::check_manifest_encoding(DigestEncoding::HexUpper, false).is_ok());
+        assert!(HashImpl::check_manifest_encoding(DigestEncoding::Base64, false).is_err());
+        assert!(HashImpl::check_manifest_encoding(DigestEncoding::Base32, true).is_ok());
+    }
+
+    #[test]
+    fn decode_input_test() {
+        assert_eq!(HashImpl::decode_input(b"de ad\nBE EF".to_vec(), "hex").unwrap(), vec![0xde, 0xad, 0xbe, 0xef]);
+        assert_eq!(HashImpl::decode_input(b"aGk=\n".to_vec(), "base64").unwrap(), b"hi".to_vec());
+        assert_eq!(HashImpl::decode_input(b"hi".to_vec(), "UTF16LE").unwrap(), vec![b'h', 0, b'i', 0]);
+        assert!(HashImpl::decode_input(b"abc".to_vec(), "hex").is_err());
+        assert!(HashImpl::decode_input(b"abc".to_vec(), "latin1").is_err());
+    }
+
+    #[test]
+    fn verify_test() {
+        let digests = vec![("sha256".to_string(), vec![0xab, 0xcd, 0xef])];
+        assert!(HashImpl::verify(&digests, "sha256=ABCDEF", DigestEncoding::Hex).is_ok());
+        assert!(HashImpl::verify(&digests, "abcdef", DigestEncoding::Hex).is_ok());
+        assert!(HashImpl::verify(&digests, "sha1=abcdef", DigestEncoding::Hex).is_err());
+        assert!(HashImpl::verify(&digests, "sha256-q83v", DigestEncoding::Sri).is_ok());
+
+        assert_eq!(HashImpl::decode_key(b"3q2+7w==\n".to_vec(), "base64").unwrap(), vec![0xde, 0xad, 0xbe, 0xef]);
+        assert_eq!(HashImpl::decode_key(b"DEADBEEF".to_vec(), "hex").unwrap(), vec![0xde, 0xad, 0xbe, 0xef]);
+    }
+}
@@ -0,0 +1,216 @@
+use std::io::{stderr, Write};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// 两次进度输出之间的最小间隔
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+
+/// 将字节数格式化为易读的字符串 (e.g. '1.50 GiB')
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{bytes} {}", UNITS[0])
+    } else {
+        format!("{value:.2} {}", UNITS[unit])
+    }
+}
+
+/// 解析十六进制字符串 (大小写不敏感), 格式不正确时返回 `None`
+pub fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.is_ascii() {
+        return None;
+    }
+    hex.as_bytes().chunks(2).map(|pair| match pair {
+        [high, low] => Some(((*high as char).to_digit(16)? * 16 + (*low as char).to_digit(16)?) as u8),
+        _ => None,
+    }).collect()
+}
+
+/// 去掉文件内容末尾的一个换行 ('\n' 或 '\r\n'), 编辑器保存的文件末尾通常都有换行
+pub fn strip_line_ending(mut raw: Vec<u8>) -> Vec<u8> {
+    if raw.ends_with(b"\n") {
+        raw.pop();
+        if raw.ends_with(b"\r") {
+            raw.pop();
+        }
+    }
+    raw
+}
+
+/// 将字符串转义为 JSON 字符串字面量 (含两侧引号)
+pub fn json_string(value: &str) -> String {
+    let mut result = String::from('"');
+    for ch in value.chars() {
+        match ch {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            ch if (ch as u32) < 0x20 => result.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => result.push(ch),
+        }
+    }
+    result.push('"');
+    result
+}
+
+/// 默认的并行线程数 (CPU 核心数)
+pub fn default_jobs() -> usize {
+    thread::available_parallelism().map_or(1, |jobs| jobs.get())
+}
+
+/// 使用至多 `jobs` 个线程并行处理 `items`, 结果的顺序与 `items` 一致
+/// 出现错误后不再分发新的任务, 返回顺序最靠前的错误
+pub fn parallel_map<T, R, F>(items: &[T], jobs: usize, f: F) -> Result<Vec<R>, String>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> Result<R, String> + Sync,
+{
+    let jobs = jobs.clamp(1, items.len().max(1));
+    if jobs == 1 {
+        return items.iter().map(f).collect();
+    }
+
+    let next = AtomicUsize::new(0);
+    let failed = AtomicBool::new(false);
+    let mut slots: Vec<Option<Result<R, String>>> = (0..items.len()).map(|_| None).collect();
+
+    thread::scope(|scope| {
+        let workers: Vec<_> = (0..jobs).map(|_| scope.spawn(|| {
+            let mut done = vec![];
+            while !failed.load(Ordering::Relaxed) {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                if index >= items.len() {
+                    break;
+                }
+                let result = f(&items[index]);
+                if result.is_err() {
+                    failed.store(true, Ordering::Relaxed);
+                }
+                done.push((index, result));
+            }
+            done
+        })).collect();
+
+        for worker in workers {
+            for (index, result) in worker.join().unwrap() {
+                slots[index] = Some(result);
+            }
+        }
+    });
+
+    // 任务按顺序分发, 因此出错位置之前的任务都已完成, 未执行的任务只会出现在错误之后
+    slots.into_iter().flatten().collect()
+}
+
+// --------------------- Progress ---------------------
+/// 大文件计算摘要时的进度及吞吐量输出 (输出到 stderr, 不影响 stdout 中的摘要及清单)
+pub struct Progress {
+    /// 文件总大小
+    total: u64,
+    /// 已处理的字节数
+    processed: u64,
+    /// 开始时间
+    started: Instant,
+    /// 上次输出进度的时间
+    last_print: Instant,
+    /// 是否已经输出过进度行
+    printed: bool,
+}
+
+impl Progress {
+    pub fn new(total: u64) -> Progress {
+        let now = Instant::now();
+        Progress { total, processed: 0, started: now, last_print: now, printed: false }
+    }
+
+    /// 平均吞吐量 (bytes/s)
+    fn throughput(&self) -> f64 {
+        let elapsed = self.started.elapsed().as_secs_f64();
+        if elapsed > 0.0 { self.processed as f64 / elapsed } else { 0.0 }
+    }
+
+    /// 记录新处理的字节数, 距离上次输出超过 `PROGRESS_INTERVAL` 时刷新进度行
+    pub fn advance(&mut self, bytes: usize) {
+        self.processed += bytes as u64;
+
+        if self.last_print.elapsed() >= PROGRESS_INTERVAL {
+            self.last_print = Instant::now();
+            self.printed = true;
+            let percent = if self.total == 0 { 100.0 } else { self.processed as f64 / self.total as f64 * 100.0 };
+            eprint!(
+                "\rProgress: {percent:.1}% ({} / {}), {}/s",
+                format_size(self.processed),
+                format_size(self.total),
+                format_size(self.throughput() as u64),
+            );
+            stderr().flush().ok();
+        }
+    }
+
+    /// 结束进度输出, 打印总耗时及平均吞吐量
+    pub fn finish(&self) {
+        if self.printed {
+            eprintln!();
+        }
+        eprintln!(
+            "Processed {} in {:.2}s ({}/s)",
+            format_size(self.processed),
+            self.started.elapsed().as_secs_f64(),
+            format_size(self.throughput() as u64),
+        );
+    }
+}
+
+#[cfg(test)]
+mod unit_test {
+    use super::*;
+
+    #[test]
+    fn parallel_map_test() {
+        let items: Vec<u32> = (0..1000).collect();
+        assert_eq!(parallel_map(&items, 8, |item| Ok(item * 2)).unwrap(), items.iter().map(|item| item * 2).collect::<Vec<_>>());
+        assert_eq!(parallel_map(&items, 8, |item| if *item >= 500 { Err(format!("{item}")) } else { Ok(*item) }), Err("500".to_string()));
+        assert!(parallel_map(&[] as &[u32], 0, |item| Ok(*item)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn format_size_test() {
+        assert_eq!(format_size(0), "0 B");
+        assert_eq!(format_size(1023), "1023 B");
+        assert_eq!(format_size(1536), "1.50 KiB");
+        assert_eq!(format_size(20 * 1024 * 1024 * 1024), "20.00 GiB");
+    }
+
+    #[test]
+    fn from_hex_test() {
+        assert_eq!(from_hex("0aFF"), Some(vec![0x0a, 0xff]));
+        assert_eq!(from_hex("abc"), None);
+        assert_eq!(from_hex("zz"), None);
+    }
+
+    #[test]
+    fn strip_line_ending_test() {
+        assert_eq!(strip_line_ending(b"key\n".to_vec()), b"key");
+        assert_eq!(strip_line_ending(b"key\r\n".to_vec()), b"key");
+        assert_eq!(strip_line_ending(b"key\n\n".to_vec()), b"key\n");
+        assert_eq!(strip_line_ending(b"key\r".to_vec()), b"key\r");
+    }
+
+    #[test]
+    fn json_string_test() {
+        assert_eq!(json_string("a/b.txt"), r#""a/b.txt""#);
+        assert_eq!(json_string("a\"b\\c\n\u{1}"), r#""a\"b\\c\n\u0001""#);
+    }
+}
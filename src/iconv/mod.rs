@@ -0,0 +1,311 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, REPLACEMENT, UTF_16BE, UTF_16LE, UTF_8};
+use walkdir::WalkDir;
+
+/// Arguments of `Commands::Iconv`
+pub struct IconvOptions {
+    pub sources: Vec<String>,
+    /// 源编码, 省略时自动检测
+    pub from: Option<String>,
+    pub to: String,
+    pub output: Option<String>,
+    /// 输出时是否添加 BOM, 仅用于 UTF-8 与 UTF-16
+    pub bom: bool,
+    /// 目录模式下只处理这些扩展名的文件 (逗号分隔, 不区分大小写)
+    pub extensions: Option<String>,
+    pub dry_run: bool,
+}
+
+/// 单个文件的转换结果
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    /// (源编码, 转换后的内容)
+    Converted(&'static Encoding, Vec<u8>),
+    /// 含有 NUL 字节且没有 BOM, 视为二进制文件
+    Binary,
+}
+
+/// 待转换的文件及其输出路径
+struct Task {
+    source: PathBuf,
+    target: PathBuf,
+}
+
+pub struct IconvImpl {}
+
+impl IconvImpl {
+    /// 解析编码名称, 支持 WHATWG Encoding Standard 中的所有标签 (如 'utf-8', 'gbk', 'gb18030', 'shift_jis', 'euc-kr', 'big5', 'windows-1252')
+    fn parse_encoding(label: &str) -> Result<&'static Encoding, String> {
+        match Encoding::for_label(label.trim().as_bytes()) {
+            Some(encoding) if encoding != REPLACEMENT => Ok(encoding),
+            _ => Err(format!("Invalid encoding '{label}'")),
+        }
+    }
+
+    /// 确定源编码: BOM 优先 (并与 `from` 核对), 其次是 `from`, 最后是自动检测 (合法的 UTF-8 或由 chardetng 推测)
+    /// 返回 (编码, BOM 长度), 二进制文件返回 `None`
+    fn detect(data: &[u8], from: Option<&'static Encoding>) -> Result<Option<(&'static Encoding, usize)>, String> {
+        if let Some((encoding, length)) = Encoding::for_bom(data) {
+            return match from {
+                Some(from) if from != encoding => Err(format!("The BOM indicates {} but the source encoding is {}", encoding.name(), from.name())),
+                _ => Ok(Some((encoding, length))),
+            };
+        }
+
+        // UTF-16 文本中 NUL 字节很常见, 其余编码的文本中不应出现
+        let utf16 = from.is_some_and(|from| from == UTF_16LE || from == UTF_16BE);
+        if !utf16 && data.contains(&0) {
+            return Ok(None);
+        }
+
+        match from {
+            Some(from) => Ok(Some((from, 0))),
+            None if std::str::from_utf8(data).is_ok() => Ok(Some((UTF_8, 0))),
+            None => {
+                let mut detector = EncodingDetector::new();
+                detector.feed(data, true);
+                Ok(Some((detector.guess(None, true), 0)))
+            }
+        }
+    }
+
+    /// 将文本编码为 `to`, 无法表示的字符视为错误
+    fn encode(text: &str, to: &'static Encoding, bom: bool) -> Result<Vec<u8>, String> {
+        // encoding_rs 遵循 Encoding Standard, 其 UTF-16 编码器输出的是 UTF-8, 因此 UTF-16 需要单独处理
+        if to == UTF_16LE || to == UTF_16BE {
+            let mut result = Vec::with_capacity(text.len() * 2 + 2);
+            for unit in std::iter::once(0xFEFF).filter(|_| bom).chain(text.encode_utf16()) {
+                result.extend(if to == UTF_16LE { unit.to_le_bytes() } else { unit.to_be_bytes() });
+            }
+            return Ok(result);
+        }
+
+        let (encoded, _, had_errors) = to.encode(text);
+        if had_errors {
+            return Err(format!("Some characters can not be represented in {}", to.name()));
+        }
+
+        let mut result = if bom && to == UTF_8 { vec![0xEF, 0xBB, 0xBF] } else { vec![] };
+        result.extend_from_slice(&encoded);
+        Ok(result)
+    }
+
+    /// 转换一个文件的内容, 源内容中的 BOM 总会被去掉, 是否在输出中添加由 `bom` 决定
+    fn convert(data: &[u8], from: Option<&'static Encoding>, to: &'static Encoding, bom: bool) -> Result<Outcome, String> {
+        let Some((encoding, bom_length)) = IconvImpl::detect(data, from)? else {
+            return Ok(Outcome::Binary);
+        };
+
+        let text = encoding.decode_without_bom_handling_and_without_replacement(&data[bom_length..])
+            .ok_or(format!("The content is not valid {}", encoding.name()))?;
+        Ok(Outcome::Converted(encoding, IconvImpl::encode(&text, to, bom)?))
+    }
+
+    /// 展开所有来源 (目录递归遍历, 跳过 '.git' 等隐藏目录) 并确定输出路径: 指定 `output` 时写入该文件 (或目录), 否则原地转换
+    fn collect(sources: &[String], output: Option<&str>, extensions: &[String]) -> Result<Vec<Task>, String> {
+        if output.is_some() && sources.len() > 1 {
+            return Err("'--output' can only be used with a single source".to_string());
+        }
+
+        let mut tasks = vec![];
+        for source in sources {
+            let root = Path::new(source);
+            if root.is_file() {
+                tasks.push(Task { source: root.to_path_buf(), target: output.map_or(root.to_path_buf(), PathBuf::from) });
+                continue;
+            }
+            if !root.is_dir() {
+                return Err(format!("{source}: No such file or directory"));
+            }
+
+            for item in WalkDir::new(root).sort_by_file_name().into_iter().filter_entry(|item| item.depth() == 0 || !is_hidden_dir(item)) {
+                let item = item.map_err(|err| format!("{err}"))?;
+                if !item.file_type().is_file() || !matches_extension(item.path(), extensions) {
+                    continue;
+                }
+                let target = match output {
+                    Some(output) => Path::new(output).join(item.path().strip_prefix(root).map_err(|err| format!("{err}"))?),
+                    None => item.path().to_path_buf(),
+                };
+                tasks.push(Task { source: item.into_path(), target });
+            }
+        }
+
+        Ok(tasks)
+    }
+
+    /// 写入转换结果: 原地转换时先写入临时文件 (复制原文件的权限) 再重命名, 失败时不会损坏原文件
+    fn write(task: &Task, content: &[u8]) -> Result<(), String> {
+        if let Some(parent) = task.target.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).map_err(|err| format!("{err}"))?;
+        }
+        if task.source != task.target {
+            return fs::write(&task.target, content).map_err(|err| format!("{err}"));
+        }
+
+        let mut temp = task.target.as_os_str().to_owned();
+        temp.push(".rstool-iconv");
+        let temp = PathBuf::from(temp);
+        fs::write(&temp, content)
+            .and_then(|_| fs::set_permissions(&temp, fs::metadata(&task.source)?.permissions()))
+            .and_then(|_| fs::rename(&temp, &task.target))
+            .map_err(|err| {
+                let _ = fs::remove_file(&temp);
+                format!("{err}")
+            })
+    }
+
+    /// 处理 Command::Iconv 子命令
+    pub fn handle(options: IconvOptions) {
+        let IconvOptions { sources, from, to, output, bom, extensions, dry_run } = options;
+        println!(
+            "[Commands::Iconv] sources = '{}', from = '{}', to = '{to}', bom = '{bom}', dry_run = '{dry_run}'",
+            sources.join(", "), from.clone().unwrap_or("auto".to_string())
+        );
+
+        let from = match from.as_deref().map(IconvImpl::parse_encoding).transpose() {
+            Ok(from) => from,
+            Err(err) => return println!("Error: {err}"),
+        };
+        let to = match IconvImpl::parse_encoding(&to) {
+            Ok(to) => to,
+            Err(err) => return println!("Error: {err}"),
+        };
+        if bom && ![UTF_8, UTF_16LE, UTF_16BE].contains(&to) {
+            return println!("Error: '--bom' only applies to UTF-8, UTF-16LE and UTF-16BE");
+        }
+        let extensions: Vec<String> = extensions.unwrap_or_default()
+            .split(',')
+            .map(|extension| extension.trim().trim_start_matches('.').to_lowercase())
+            .filter(|extension| !extension.is_empty())
+            .collect();
+        let tasks = match IconvImpl::collect(&sources, output.as_deref(), &extensions) {
+            Ok(tasks) => tasks,
+            Err(err) => return println!("Error: {err}"),
+        };
+
+        let (mut converted, mut skipped, mut failed) = (0, 0, 0);
+        for task in &tasks {
+            let path = task.source.display();
+            let result = fs::read(&task.source).map_err(|err| format!("{err}"))
+                .and_then(|data| IconvImpl::convert(&data, from, to, bom).map(|outcome| (data, outcome)));
+
+            match result {
+                Ok((_, Outcome::Binary)) => {
+                    skipped += 1;
+                    println!("{path}: skipped (binary)");
+                }
+                // 原地转换且内容没有变化时不必重写文件
+                Ok((data, Outcome::Converted(encoding, content))) if content == data && task.source == task.target => {
+                    skipped += 1;
+                    println!("{path}: unchanged ({})", encoding.name());
+                }
+                Ok((_, Outcome::Converted(encoding, content))) => {
+                    match if dry_run { Ok(()) } else { IconvImpl::write(task, &content) } {
+                        Ok(_) => {
+                            converted += 1;
+                            println!("{path}: {} -> {}", encoding.name(), to.name());
+                        }
+                        Err(err) => {
+                            failed += 1;
+                            println!("{path}: failed ({err})");
+                        }
+                    }
+                }
+                Err(err) => {
+                    failed += 1;
+                    println!("{path}: failed ({err})");
+                }
+            }
+        }
+
+        let verb = if dry_run { "to be converted" } else { "converted" };
+        if failed > 0 {
+            println!("Error: {failed} failed, {converted} {verb}, {skipped} skipped");
+            std::process::exit(1);
+        }
+        println!("Ok: {converted} {verb}, {skipped} skipped");
+    }
+}
+
+/// 是否为隐藏目录 (名称以 '.' 开头, e.g. '.git', '.idea'), 目录模式下不转换其中的文件
+fn is_hidden_dir(item: &walkdir::DirEntry) -> bool {
+    item.file_type().is_dir() && item.file_name().to_string_lossy().starts_with('.')
+}
+
+/// 文件扩展名是否在列表中 (列表为空时匹配所有文件)
+fn matches_extension(path: &Path, extensions: &[String]) -> bool {
+    extensions.is_empty() || path.extension().is_some_and(|extension| extensions.contains(&extension.to_string_lossy().to_lowercase()))
+}
+
+#[cfg(test)]
+mod unit_test {
+    use super::*;
+    use encoding_rs::{GBK, SHIFT_JIS};
+
+    fn convert(data: &[u8], from: Option<&str>, to: &str, bom: bool) -> Result<Outcome, String> {
+        let from = from.map(|from| IconvImpl::parse_encoding(from).unwrap());
+        IconvImpl::convert(data, from, IconvImpl::parse_encoding(to).unwrap(), bom)
+    }
+
+    #[test]
+    fn convert_test() {
+        // '中文' 的 GBK 编码
+        let gbk = [0xD6, 0xD0, 0xCE, 0xC4];
+        assert_eq!(convert(&gbk, Some("gbk"), "utf-8", false), Ok(Outcome::Converted(GBK, "中文".as_bytes().to_vec())));
+        assert_eq!(convert("中文".as_bytes(), None, "gb18030", false), Ok(Outcome::Converted(UTF_8, gbk.to_vec())));
+        // BOM 决定源编码并被去掉, 输出时按需添加
+        assert_eq!(convert(&[0xFF, 0xFE, 0x2D, 0x4E], None, "utf-8", false), Ok(Outcome::Converted(UTF_16LE, "中".as_bytes().to_vec())));
+        assert_eq!(convert("中".as_bytes(), None, "utf-16be", true), Ok(Outcome::Converted(UTF_8, vec![0xFE, 0xFF, 0x4E, 0x2D])));
+        assert_eq!(convert(&[0xEF, 0xBB, 0xBF, b'a'], None, "utf-8", false), Ok(Outcome::Converted(UTF_8, b"a".to_vec())));
+        assert!(convert(&[0xFF, 0xFE, 0x2D, 0x4E], Some("utf-8"), "utf-8", false).is_err());
+
+        assert_eq!(convert(&[b'a', 0, b'b'], None, "utf-8", false), Ok(Outcome::Binary));
+        assert!(convert("中".as_bytes(), None, "windows-1252", false).is_err());
+        assert!(convert(&[0x81], Some("shift_jis"), "utf-8", false).is_err());
+        assert!(IconvImpl::parse_encoding("iso-2022-kr").is_err());
+    }
+
+    #[test]
+    fn detect_test() {
+        let text = "日本語のテキストファイルを変換します。文字コードは自動的に判定されます。";
+        let (sjis, _, _) = SHIFT_JIS.encode(text);
+        assert_eq!(convert(&sjis, None, "utf-8", false), Ok(Outcome::Converted(SHIFT_JIS, text.as_bytes().to_vec())));
+    }
+
+    #[test]
+    fn collect_test() {
+        let dir = std::env::temp_dir().join("rstool_iconv_collect_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join(".git")).unwrap();
+        fs::write(dir.join(".git").join("config"), "[core]").unwrap();
+        fs::write(dir.join(".editorconfig"), "root = true").unwrap();
+        fs::write(dir.join("a.txt"), "a").unwrap();
+
+        // 隐藏目录被跳过, 隐藏文件不受影响
+        let tasks = IconvImpl::collect(&[dir.to_string_lossy().to_string()], None, &[]).unwrap();
+        let names: Vec<String> = tasks.iter().map(|task| task.source.file_name().unwrap().to_string_lossy().to_string()).collect();
+        assert_eq!(names, vec![".editorconfig", "a.txt"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// 原地转换保留原文件的权限
+    #[cfg(unix)]
+    #[test]
+    fn write_test() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = std::env::temp_dir().join("rstool_iconv_write_test.sh");
+        fs::write(&path, "echo").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+
+        IconvImpl::write(&Task { source: path.clone(), target: path.clone() }, b"echo hi").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"echo hi");
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o755);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
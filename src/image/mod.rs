@@ -1,22 +1,87 @@
 use std::path::{Path, PathBuf};
-use crate::image::utils::{parse_image, ParsedImage, to_image};
+use image::ImageFormat;
+use crate::image::utils::{parse_filter, parse_image, parse_png_compression, parse_png_filter, EncodeOptions, Fit, Gravity, ParsedImage, ResizeOptions, TiffCompression, ToneMap, to_image, is_writable};
 
 mod utils;
 
-/// Try to parse the format from the file extension
+/// Arguments of `Commands::Image`
+pub struct ImageOptions {
+    pub source: String,
+    pub format: Option<String>,
+    pub size: Option<String>,
+    /// Path of the output image, `<stem>[@WxH].<format>` next to the source if omitted
+    pub output: Option<String>,
+    /// Resampling filter used when resizing
+    pub filter: String,
+    /// How the image is fitted into the target size
+    pub fit: String,
+    /// Which part is kept when cropping (`cover`) or where the image is placed when padding (`contain`)
+    pub gravity: String,
+    /// Quality (1-100) of the lossy formats
+    pub quality: Option<u8>,
+    /// Whether to write a progressive JPEG
+    pub progressive: bool,
+    /// Whether to write a lossless WebP
+    pub lossless: bool,
+    /// AVIF encoding speed (1-10)
+    pub speed: Option<u8>,
+    pub png_compression: Option<String>,
+    pub png_filter: Option<String>,
+    pub tiff_compression: Option<String>,
+    /// How HDR images are mapped to 8/16-bit targets
+    pub tone_map: String,
+    /// Exposure adjustment in stops applied before tone mapping
+    pub exposure: f32,
+}
+
+/// Try to parse the format from the file extension (case insensitive)
 fn try_parse_format(source: &Path) -> Option<String> {
-    match source.extension() {
-        Some(ext) => match ext.to_str() {
-            Some("bmp") => Some("bmp".to_string()),
-            Some("gif") => Some("gif".to_string()),
-            Some("ico") => Some("ico".to_string()),
-            Some("jpg" | "jpeg") => Some("jpg".to_string()),
-            Some("png") => Some("png".to_string()),
-            Some("tiff") => Some("tiff".to_string()),
-            _ => None,
-        }
-        None => None,
+    let ext = source.extension()?.to_str()?.to_lowercase();
+    match ext.as_str() {
+        "bmp" | "gif" | "ico" | "png" | "webp" | "avif" | "qoi" | "tga" | "pbm" | "pgm" | "ppm" | "pam" | "dds" | "hdr" | "exr" | "ff" => Some(ext),
+        "jpg" | "jpeg" => Some("jpg".to_string()),
+        "tif" | "tiff" => Some("tiff".to_string()),
+        // `.pnm` may contain any of the PNM subtypes
+        "pnm" => Some("pnm".to_string()),
+        _ => None,
+    }
+}
+
+/// Try to detect the format from the magic bytes of the file content (TGA has no magic bytes)
+fn try_detect_format(raw: &[u8]) -> Option<String> {
+    // `image` only knows two sizes of the `ftyp` box, check the brand of any AVIF file (e.g. the ones written by ravif)
+    if raw.len() >= 12 && &raw[4..8] == b"ftyp" && matches!(&raw[8..12], b"avif" | b"avis") {
+        return Some("avif".to_string());
     }
+    let format = match image::guess_format(raw).ok()? {
+        ImageFormat::Bmp => "bmp",
+        ImageFormat::Gif => "gif",
+        ImageFormat::Ico => "ico",
+        ImageFormat::Jpeg => "jpg",
+        ImageFormat::Png => "png",
+        ImageFormat::Tiff => "tiff",
+        ImageFormat::WebP => "webp",
+        ImageFormat::Avif => "avif",
+        ImageFormat::Qoi => "qoi",
+        ImageFormat::Dds => "dds",
+        ImageFormat::Hdr => "hdr",
+        ImageFormat::OpenExr => "exr",
+        ImageFormat::Farbfeld => "ff",
+        // The second byte of the magic number tells the PNM subtype
+        ImageFormat::Pnm => match raw[1] {
+            b'1' | b'4' => "pbm",
+            b'2' | b'5' => "pgm",
+            b'3' | b'6' => "ppm",
+            _ => "pam",
+        },
+        _ => return None,
+    };
+    Some(format.to_string())
+}
+
+/// Whether the format of the file extension agrees with the format of the content
+fn is_same_format(ext_format: &str, format: &str) -> bool {
+    ext_format == format || (ext_format == "pnm" && ["pbm", "pgm", "ppm", "pam"].contains(&format))
 }
 
 /// Try to parse the size from the string
@@ -63,28 +128,112 @@ pub struct ImageImpl {}
 impl ImageImpl {
     /// Read the image from the path, get the format and the parsed image
     fn read_image(source: &Path) -> Result<(String, ParsedImage), String> {
-        // Try to parse the format from the file extension if it is not provided
-        match try_parse_format(source) {
-            Some(source_format) => {
-                // Read the raw data from the source file
-                let file_raw = match std::fs::read(source) {
-                    Ok(raw_file) => raw_file,
-                    Err(err) => return Err(format!("{err}"))
-                };
-
-                parse_image(file_raw).map(|parsed_image| (source_format, parsed_image))
+        // Read the raw data from the source file
+        let file_raw = match std::fs::read(source) {
+            Ok(raw_file) => raw_file,
+            Err(err) => return Err(format!("{err}"))
+        };
+
+        // Trust the content over the file extension, which is only used for the formats without magic bytes
+        let ext_format = try_parse_format(source);
+        let source_format = match (try_detect_format(&file_raw), ext_format) {
+            (Some(format), Some(ext_format)) => {
+                if !is_same_format(&ext_format, &format) {
+                    println!("WARNING: The file extension indicates '{ext_format}' but the content is '{format}', read as '{format}'");
+                }
+                format
             }
-            None => Err(format!("Invalid format. (Could not parse format from file extension)"))
+            (Some(format), None) => format,
+            (None, Some(ext_format)) => ext_format,
+            (None, None) => return Err("Invalid format. (Could not detect the format from the file content or extension)".to_string()),
+        };
+
+        parse_image(file_raw, &source_format).map(|parsed_image| (source_format, parsed_image))
+    }
+
+    /// The target format: `--format` if given, otherwise the source format, or `png` if the source format can only be read (e.g. DDS)
+    fn target_format(format: Option<&str>, source_format: String) -> String {
+        match format {
+            Some(format) => format.to_lowercase(),
+            None if is_writable(&source_format) => source_format,
+            None => "png".to_string(),
+        }
+    }
+
+    /// The output path: `--output` if given, otherwise `<stem>[@WxH].<format>` next to the source
+    /// Refuse to overwrite the source image (e.g. `photo.jpg -f jpg -q 80`, or `PHOTO.JPG` on case-insensitive file systems)
+    fn target_path(output: Option<&str>, source: &Path, target_size: Option<(u32, u32)>, target_format: &str) -> Result<PathBuf, String> {
+        let target_path = match output {
+            Some(output) => PathBuf::from(output),
+            None => {
+                let target_stem = source.file_stem().map_or("", |stem| stem.to_str().unwrap_or("unknown")).to_string();
+                let mut target_path = PathBuf::from(source.parent().unwrap_or(Path::new("")));
+                target_path.push(format!("{}{}.{}", target_stem, normalized_size(target_size), target_format));
+                target_path
+            }
+        };
+
+        // Compare the files rather than the paths, which also covers links and case-insensitive file systems
+        match same_file::is_same_file(source, &target_path) {
+            Ok(true) => Err(format!("The output {target_path:?} is the source image, use '--output' to write it elsewhere")),
+            _ => Ok(target_path),
+        }
+    }
+
+    /// Parse the resize options, `None` if the size is omitted
+    fn resize_options(options: &ImageOptions, w: u32, h: u32) -> Result<Option<ResizeOptions>, String> {
+        let filter = parse_filter(&options.filter)?;
+        let fit = Fit::parse(&options.fit)?;
+        let gravity = Gravity::parse(&options.gravity)?;
+
+        match calc_final_size(try_parse_size(options.size.clone()), w, h) {
+            Some((0, _) | (_, 0)) => Err("Invalid size. (The width and height must be greater than 0)".to_string()),
+            Some(size) => Ok(Some(ResizeOptions { size, filter, fit, gravity })),
+            None => Ok(None),
+        }
+    }
+
+    /// Whether any encoder option is given, which asks for a re-encode even without `--format` and `--size`
+    fn has_encoder_options(options: &ImageOptions) -> bool {
+        options.quality.is_some() || options.progressive || options.lossless || options.speed.is_some()
+            || options.png_compression.is_some() || options.png_filter.is_some() || options.tiff_compression.is_some()
+    }
+
+    /// Parse the encoder settings of the output formats, the omitted ones fall back to the defaults
+    fn encode_options(options: &ImageOptions) -> Result<EncodeOptions, String> {
+        let defaults = EncodeOptions::default();
+        let quality = options.quality.unwrap_or(defaults.quality);
+        if !(1..=100).contains(&quality) {
+            return Err(format!("Invalid quality. (Expect a value between 1 and 100, Got: {quality})"));
         }
+        let speed = options.speed.unwrap_or(defaults.speed);
+        if !(1..=10).contains(&speed) {
+            return Err(format!("Invalid speed. (Expect a value between 1 and 10, Got: {speed})"));
+        }
+
+        Ok(EncodeOptions {
+            quality,
+            progressive: options.progressive,
+            lossless: options.lossless,
+            speed,
+            png_compression: options.png_compression.as_deref().map_or(Ok(defaults.png_compression), parse_png_compression)?,
+            png_filter: options.png_filter.as_deref().map_or(Ok(defaults.png_filter), parse_png_filter)?,
+            tiff_compression: options.tiff_compression.as_deref().map_or(Ok(defaults.tiff_compression), TiffCompression::parse)?,
+            tone_map: ToneMap::parse(&options.tone_map)?,
+            exposure: options.exposure,
+        })
     }
 
-    pub fn handle(source: String, format: Option<String>, size: Option<String>) {
-        println!("[Commands::Image] source = '{source}', format = '{format}', size = '{size}'",
-                 format = format.clone().unwrap_or("default".to_string()),
-                 size = size.clone().unwrap_or("default".to_string())
+    pub fn handle(options: ImageOptions) {
+        println!("[Commands::Image] source = '{source}', format = '{format}', size = '{size}', filter = '{filter}', fit = '{fit}'",
+                 source = options.source,
+                 format = options.format.clone().unwrap_or("default".to_string()),
+                 size = options.size.clone().unwrap_or("default".to_string()),
+                 filter = options.filter,
+                 fit = options.fit,
         );
 
-        let source_path = Path::new(&source);
+        let source_path = Path::new(&options.source);
 
         if !source_path.exists() {
             println!("Error: Source file does not exist");
@@ -95,16 +244,26 @@ impl ImageImpl {
                 Ok((source_format, parsed_image)) => {
                     println!("Image meta: {}", parsed_image.meta.to_string());
 
-                    if format.is_some() || size.is_some() {
-                        let target_format = format.unwrap_or(source_format);
-                        let target_size = calc_final_size(try_parse_size(size), parsed_image.meta.w, parsed_image.meta.h);
+                    // An encoder option alone re-encodes the image in its own format (e.g. `photo.jpg -q 80 -o small.jpg`)
+                    if options.format.is_some() || options.size.is_some() || ImageImpl::has_encoder_options(&options) {
+                        let target_format = ImageImpl::target_format(options.format.as_deref(), source_format);
+                        let resize_options = match ImageImpl::resize_options(&options, parsed_image.meta.w, parsed_image.meta.h) {
+                            Ok(resize_options) => resize_options,
+                            Err(err) => return println!("Error: {err}"),
+                        };
+                        let encode_options = match ImageImpl::encode_options(&options) {
+                            Ok(encode_options) => encode_options,
+                            Err(err) => return println!("Error: {err}"),
+                        };
+                        let target_size = resize_options.as_ref().map(|resize_options| resize_options.size);
+                        let target_path = match ImageImpl::target_path(options.output.as_deref(), source_path, target_size, &target_format) {
+                            Ok(target_path) => target_path,
+                            Err(err) => return println!("Error: {err}"),
+                        };
 
-                        match to_image(parsed_image.dyn_image, target_size, &target_format) {
+                        match to_image(parsed_image.dyn_image, resize_options, &target_format, &encode_options) {
                             Ok(image_buffer) => {
-                                let target_stem = source_path.file_stem().map_or("", |stem| stem.to_str().unwrap_or("unknown")).to_string();
                                 // write to file
-                                let mut target_path = PathBuf::from(source_path.parent().unwrap_or(Path::new("")));
-                                target_path.push(format!("{}{}.{}", target_stem, normalized_size(target_size), target_format));
                                 match std::fs::write(&target_path, image_buffer) {
                                     Ok(_) => println!("Ok. (Image generated successfully at '{:?}')", target_path),
                                     Err(write_err) => println!("Error: {write_err}")
@@ -124,6 +283,10 @@ impl ImageImpl {
 #[cfg(test)]
 mod unit_test {
     use std::path::{PathBuf};
+    use image::{DynamicImage, GenericImageView, Rgb, Rgb32FImage, Rgba, RgbaImage};
+    use image::imageops::FilterType;
+    use crate::image::utils::{parse_image, resize, to_image, EncodeOptions, Fit, Gravity, ResizeOptions, TiffCompression, ToneMap};
+    use super::{is_same_format, try_detect_format, try_parse_format, ImageImpl, ImageOptions};
 
     #[test]
     fn tt() {
@@ -131,4 +294,213 @@ mod unit_test {
 
         println!("{}", p);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn resize_test() {
+        // 4x2: left half red, right half blue
+        let source = DynamicImage::ImageRgba8(RgbaImage::from_fn(4, 2, |x, _| if x < 2 { Rgba([255, 0, 0, 255]) } else { Rgba([0, 0, 255, 255]) }));
+        let run = |fit: Fit, gravity: Gravity| resize(source.clone(), &ResizeOptions { size: (2, 2), filter: FilterType::Nearest, fit, gravity });
+
+        assert_eq!(run(Fit::Inside, Gravity::Center).dimensions(), (2, 1));
+        assert_eq!(run(Fit::Outside, Gravity::Center).dimensions(), (4, 2));
+        assert_eq!(run(Fit::Fill, Gravity::Center).dimensions(), (2, 2));
+        // cover keeps the part pointed by the gravity
+        assert_eq!(run(Fit::Cover, Gravity::West).get_pixel(1, 1), Rgba([255, 0, 0, 255]));
+        assert_eq!(run(Fit::Cover, Gravity::East).get_pixel(0, 0), Rgba([0, 0, 255, 255]));
+        // contain pads the rest with transparent pixels
+        let contained = run(Fit::Contain, Gravity::North);
+        assert_eq!(contained.dimensions(), (2, 2));
+        assert_eq!(contained.get_pixel(0, 0), Rgba([255, 0, 0, 255]));
+        assert_eq!(contained.get_pixel(0, 1), Rgba([0, 0, 0, 0]));
+    }
+
+    #[test]
+    fn encode_test() {
+        let source = DynamicImage::ImageRgba8(RgbaImage::from_fn(64, 64, |x, y| Rgba([(x * 4) as u8, (y * 4) as u8, 128, 255])));
+        let encode = |format: &str, options: EncodeOptions| to_image(source.clone(), None, format, &options).unwrap();
+
+        // lossless formats round trip with any compression
+        for tiff_compression in [TiffCompression::None, TiffCompression::Lzw, TiffCompression::Deflate, TiffCompression::Packbits] {
+            let tiff = encode("tiff", EncodeOptions { tiff_compression, ..EncodeOptions::default() });
+            assert_eq!(image::load_from_memory(&tiff).unwrap().to_rgba8(), source.to_rgba8());
+        }
+
+        let low = encode("jpg", EncodeOptions { quality: 10, ..EncodeOptions::default() });
+        let high = encode("jpg", EncodeOptions { quality: 100, ..EncodeOptions::default() });
+        assert!(low.len() < high.len());
+        let progressive = encode("jpg", EncodeOptions { progressive: true, ..EncodeOptions::default() });
+        assert_eq!(image::load_from_memory(&progressive).unwrap().dimensions(), (64, 64));
+    }
+
+    #[test]
+    fn modern_format_test() {
+        // libwebp drops the color of fully transparent pixels, so keep it opaque
+        let source = DynamicImage::ImageRgba8(RgbaImage::from_fn(16, 16, |x, y| Rgba([(x * 16) as u8, (y * 16) as u8, 128, 255])));
+        let encode = |format: &str, options: EncodeOptions| to_image(source.clone(), None, format, &options).unwrap();
+
+        let lossless = encode("webp", EncodeOptions { lossless: true, ..EncodeOptions::default() });
+        assert_eq!(image::load_from_memory(&lossless).unwrap().to_rgba8(), source.to_rgba8());
+        let lossy = encode("webp", EncodeOptions::default());
+        assert_eq!(image::load_from_memory(&lossy).unwrap().dimensions(), (16, 16));
+
+        let avif = encode("avif", EncodeOptions { quality: 100, speed: 10, ..EncodeOptions::default() });
+        assert_eq!(&avif[4..12], b"ftypavif");
+        assert_eq!(try_detect_format(&avif), Some("avif".to_string()));
+        // ravif writes 10-bit AVIF which is read as 16-bit, and it is lossy even at quality 100, compare with a tolerance
+        let decoded = parse_image(avif, "avif").unwrap().dyn_image;
+        assert_eq!(decoded.color(), image::ColorType::Rgb16);
+        let max_diff = decoded.to_rgba8().as_raw().iter().zip(source.to_rgba8().as_raw()).map(|(a, b)| a.abs_diff(*b)).max().unwrap();
+        assert!(max_diff <= 8, "{max_diff}");
+
+        // the alpha channel is a separate AV1 item
+        let transparent = DynamicImage::ImageRgba8(RgbaImage::from_fn(16, 16, |x, y| Rgba([255, 0, 0, (x * 16 + y) as u8])));
+        let avif = to_image(transparent.clone(), None, "avif", &EncodeOptions { quality: 100, speed: 10, ..EncodeOptions::default() }).unwrap();
+        let decoded = parse_image(avif, "avif").unwrap().dyn_image;
+        assert_eq!(decoded.color(), image::ColorType::Rgba16);
+        let max_alpha_diff = decoded.to_rgba8().pixels().zip(transparent.to_rgba8().pixels()).map(|(a, b)| a[3].abs_diff(b[3])).max().unwrap();
+        assert!(max_alpha_diff <= 8, "{max_alpha_diff}");
+    }
+
+    #[test]
+    fn raster_format_test() {
+        let source = DynamicImage::ImageRgba8(RgbaImage::from_fn(8, 8, |x, y| Rgba([(x * 32) as u8, (y * 32) as u8, 128, 255])));
+        for format in ["qoi", "tga", "ff", "ppm"] {
+            let encoded = to_image(source.clone(), None, format, &EncodeOptions::default()).unwrap();
+            assert_eq!(parse_image(encoded, format).unwrap().dyn_image.to_rgba8(), source.to_rgba8(), "{format}");
+        }
+        // the PNM decoder can not read PAM with alpha
+        let rgb = DynamicImage::ImageRgb8(source.to_rgb8());
+        let pam = to_image(rgb.clone(), None, "pam", &EncodeOptions::default()).unwrap();
+        assert_eq!(parse_image(pam, "pam").unwrap().dyn_image, rgb);
+        let gray = to_image(source.clone(), None, "pgm", &EncodeOptions::default()).unwrap();
+        assert_eq!(image::load_from_memory(&gray).unwrap().color(), image::ColorType::L8);
+        assert!(to_image(source.clone(), None, "dds", &EncodeOptions::default()).is_err());
+        assert_eq!(ImageImpl::target_format(None, "dds".to_string()), "png");
+        assert_eq!(ImageImpl::target_format(None, "pbm".to_string()), "pbm");
+        assert_eq!(ImageImpl::target_format(Some("JPG"), "dds".to_string()), "jpg");
+
+        // PBM: dark pixels become black, rows are padded to whole bytes, an all black image is fine too
+        let pbm = to_image(source, None, "pbm", &EncodeOptions::default()).unwrap();
+        assert_eq!(&pbm[..7], b"P4\n8 8\n");
+        let bitmap = parse_image(pbm, "pbm").unwrap().dyn_image.to_luma8();
+        assert_eq!(bitmap.get_pixel(0, 0)[0], 0);
+        assert_eq!(bitmap.get_pixel(7, 7)[0], 255);
+        let black = DynamicImage::ImageRgb8(image::RgbImage::new(3, 2));
+        let pbm = to_image(black, None, "pbm", &EncodeOptions::default()).unwrap();
+        assert_eq!(pbm, b"P4\n3 2\n\xe0\xe0");
+    }
+
+    #[test]
+    fn tone_map_test() {
+        // linear 1.0 (reinhard -> 0.5, sRGB -> 0.735), 4.0 (clipped by clamp)
+        let hdr = DynamicImage::ImageRgb32F(Rgb32FImage::from_fn(2, 1, |x, _| if x == 0 { Rgb([1.0; 3]) } else { Rgb([4.0; 3]) }));
+
+        for format in ["hdr", "exr"] {
+            let encoded = to_image(hdr.clone(), None, format, &EncodeOptions::default()).unwrap();
+            assert_eq!(parse_image(encoded, format).unwrap().dyn_image.to_rgb32f().get_pixel(1, 0), &Rgb([4.0; 3]), "{format}");
+        }
+
+        let png = |tone_map: ToneMap, exposure: f32| {
+            let encoded = to_image(hdr.clone(), None, "png", &EncodeOptions { tone_map, exposure, ..EncodeOptions::default() }).unwrap();
+            image::load_from_memory(&encoded).unwrap().to_rgb8()
+        };
+        assert_eq!(png(ToneMap::Reinhard, 0.0).get_pixel(0, 0), &Rgb([188; 3]));
+        assert_eq!(png(ToneMap::Clamp, 0.0).get_pixel(1, 0), &Rgb([255; 3]));
+        assert_eq!(png(ToneMap::Clamp, -2.0).get_pixel(1, 0), &Rgb([255; 3]));
+        assert_eq!(png(ToneMap::Clamp, -3.0).get_pixel(1, 0), &Rgb([188; 3]));
+
+        // 16-bit capable formats keep 16 bits per sample
+        for format in ["png", "tiff"] {
+            let encoded = to_image(hdr.clone(), None, format, &EncodeOptions::default()).unwrap();
+            assert_eq!(image::load_from_memory(&encoded).unwrap().color(), image::ColorType::Rgb16, "{format}");
+        }
+    }
+
+    #[test]
+    fn detect_format_test() {
+        assert_eq!(try_parse_format(&PathBuf::from("PHOTO.JPG")), Some("jpg".to_string()));
+        assert_eq!(try_parse_format(&PathBuf::from("scan.Tif")), Some("tiff".to_string()));
+        assert_eq!(try_parse_format(&PathBuf::from("blob")), None);
+        assert!(is_same_format("pnm", "pgm"));
+        assert!(!is_same_format("ppm", "pgm"));
+
+        let source = DynamicImage::ImageRgba8(RgbaImage::from_fn(4, 4, |x, y| Rgba([(x * 64) as u8, (y * 64) as u8, 128, 255])));
+        let encode = |format: &str| to_image(source.clone(), None, format, &EncodeOptions::default()).unwrap();
+        for format in ["png", "jpg", "gif", "bmp", "tiff", "webp", "qoi", "ff", "pgm", "ppm", "exr"] {
+            assert_eq!(try_detect_format(&encode(format)), Some(format.to_string()));
+        }
+        // TGA has no magic bytes
+        assert_eq!(try_detect_format(&encode("tga")), None);
+
+        let dir = std::env::temp_dir().join("rstool_image_detect_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        // a PNG without extension, a PNG named as JPEG, and a TGA that can only be told by the extension
+        std::fs::write(dir.join("blob"), encode("png")).unwrap();
+        std::fs::write(dir.join("PHOTO.JPG"), encode("png")).unwrap();
+        std::fs::write(dir.join("image.TGA"), encode("tga")).unwrap();
+        std::fs::write(dir.join("text"), "not an image").unwrap();
+
+        assert_eq!(ImageImpl::read_image(&dir.join("blob")).unwrap().0, "png");
+        assert_eq!(ImageImpl::read_image(&dir.join("PHOTO.JPG")).unwrap().0, "png");
+        assert_eq!(ImageImpl::read_image(&dir.join("image.TGA")).unwrap().0, "tga");
+        assert!(ImageImpl::read_image(&dir.join("text")).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Any encoder option asks for a re-encode, the omitted ones fall back to the defaults
+    #[test]
+    fn encoder_options_test() {
+        let options = ImageOptions {
+            source: "photo.jpg".to_string(),
+            format: None,
+            size: None,
+            output: None,
+            filter: "lanczos3".to_string(),
+            fit: "inside".to_string(),
+            gravity: "center".to_string(),
+            quality: None,
+            progressive: false,
+            lossless: false,
+            speed: None,
+            png_compression: None,
+            png_filter: None,
+            tiff_compression: None,
+            tone_map: "reinhard".to_string(),
+            exposure: 0.0,
+        };
+        assert!(!ImageImpl::has_encoder_options(&options));
+        let defaults = ImageImpl::encode_options(&options).unwrap();
+        assert_eq!((defaults.quality, defaults.speed, defaults.tiff_compression), (80, 4, TiffCompression::None));
+
+        let quality = ImageOptions { quality: Some(60), ..options };
+        assert!(ImageImpl::has_encoder_options(&quality));
+        assert_eq!(ImageImpl::encode_options(&quality).unwrap().quality, 60);
+        let tiff = ImageOptions { quality: None, tiff_compression: Some("LZW".to_string()), ..quality };
+        assert!(ImageImpl::has_encoder_options(&tiff));
+        assert_eq!(ImageImpl::encode_options(&tiff).unwrap().tiff_compression, TiffCompression::Lzw);
+        let progressive = ImageOptions { tiff_compression: None, progressive: true, ..tiff };
+        assert!(ImageImpl::has_encoder_options(&progressive));
+        assert!(ImageImpl::encode_options(&ImageOptions { speed: Some(11), ..progressive }).is_err());
+    }
+
+    /// The source image is never the output, whether the path is derived or given by `--output`
+    #[test]
+    fn target_path_test() {
+        let dir = std::env::temp_dir().join("rstool_image_target_path_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("photo.jpg");
+        std::fs::write(&source, "jpeg").unwrap();
+
+        assert!(ImageImpl::target_path(None, &source, None, "jpg").is_err());
+        assert_eq!(ImageImpl::target_path(None, &source, Some((32, 16)), "jpg").unwrap(), dir.join("photo@32x16.jpg"));
+        assert_eq!(ImageImpl::target_path(None, &source, None, "webp").unwrap(), dir.join("photo.webp"));
+        assert!(ImageImpl::target_path(Some(source.to_str().unwrap()), &source, Some((32, 16)), "jpg").is_err());
+        let output = dir.join("small.jpg");
+        assert_eq!(ImageImpl::target_path(Some(output.to_str().unwrap()), &source, None, "jpg").unwrap(), output);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
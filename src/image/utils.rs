@@ -1,6 +1,18 @@
 use std::io::Cursor;
-use image::{ColorType, DynamicImage, ImageOutputFormat};
-use image::imageops::FilterType;
+use image::{ColorType, DynamicImage, GenericImageView, ImageBuffer, ImageEncoder, ImageFormat, ImageOutputFormat, Rgb32FImage};
+use image::codecs::hdr::{HdrDecoder, HdrEncoder, HdrMetadata};
+use image::codecs::png::{CompressionType, FilterType as PngFilterType, PngEncoder};
+use image::codecs::pnm::{PnmSubtype, SampleEncoding};
+use image::imageops::{self, FilterType};
+use ravif::Img;
+use re_rav1d::dav1d::{pixel, Decoder as Av1Decoder, Picture, PixelLayout, PlanarImageComponent, Settings as Av1Settings};
+use tiff::encoder::colortype::{Gray16, Gray8, RGB16, RGB8, RGBA16, RGBA8};
+use tiff::encoder::compression::{Compression, Deflate, Lzw, Packbits, Uncompressed};
+
+/// The default quality of the lossy formats, the same as sharp
+pub const DEFAULT_QUALITY: u8 = 80;
+/// The default AVIF encoding speed (1 = slowest and smallest, 10 = fastest)
+pub const DEFAULT_SPEED: u8 = 4;
 
 #[derive(Debug)]
 pub struct ImageMeta {
@@ -29,8 +41,18 @@ pub struct ParsedImage {
     pub dyn_image: DynamicImage,
 }
 
-pub fn parse_image(img_buffer: Vec<u8>) -> Result<ParsedImage, String> {
-    match image::load_from_memory(&img_buffer) {
+pub fn parse_image(img_buffer: Vec<u8>, format: &str) -> Result<ParsedImage, String> {
+    // Some formats (e.g. tga) can not be detected from the content, so decode with the known format
+    let loaded = match ImageFormat::from_extension(format) {
+        // The decoder of `image` converts Radiance HDR to 8-bit, read the floating point samples instead
+        Some(ImageFormat::Hdr) => read_hdr(&img_buffer),
+        // `image` 0.24 can only decode AVIF with the native dav1d library, read it with rav1d instead
+        Some(ImageFormat::Avif) => read_avif(&img_buffer),
+        Some(image_format) => image::load_from_memory_with_format(&img_buffer, image_format).map_err(|err| format!("{err}")),
+        None => image::load_from_memory(&img_buffer).map_err(|err| format!("{err}")),
+    };
+
+    match loaded {
         Ok(img) => {
             Ok(ParsedImage {
                 meta: ImageMeta::new(
@@ -42,36 +64,618 @@ pub fn parse_image(img_buffer: Vec<u8>) -> Result<ParsedImage, String> {
                 dyn_image: img,
             })
         }
-        Err(err) => Err(format!("{err}"))
+        Err(err) => Err(err)
+    }
+}
+
+fn read_hdr(img_buffer: &[u8]) -> Result<DynamicImage, String> {
+    let decoder = HdrDecoder::new(img_buffer).map_err(|err| format!("{err}"))?;
+    let HdrMetadata { width, height, .. } = decoder.metadata();
+    let pixels = decoder.read_image_hdr().map_err(|err| format!("{err}"))?;
+
+    match Rgb32FImage::from_raw(width, height, pixels.iter().flat_map(|pixel| pixel.0).collect()) {
+        Some(img) => Ok(DynamicImage::ImageRgb32F(img)),
+        None => Err("Invalid HDR image. (The pixel data does not match the dimension)".to_string())
+    }
+}
+
+/// AVIF is read by avif-parse (the container) and rav1d (a pure Rust port of dav1d), 8-bit images are read as 8-bit
+/// and 10/12-bit images as 16-bit, with an alpha channel if the file has an alpha item
+fn read_avif(img_buffer: &[u8]) -> Result<DynamicImage, String> {
+    let avif = avif_parse::read_avif(&mut Cursor::new(img_buffer)).map_err(|err| format!("Invalid AVIF image. ({err:?})"))?;
+    let color = decode_av1(&avif.primary_item)?;
+    let alpha = match &avif.alpha_item {
+        Some(alpha_item) => Some(decode_av1(alpha_item)?),
+        None => None,
+    };
+
+    let (w, h) = (color.width(), color.height());
+    if alpha.as_ref().is_some_and(|alpha| (alpha.width(), alpha.height()) != (w, h)) {
+        return Err("Invalid AVIF image. (The alpha channel does not match the dimension)".to_string());
+    }
+
+    let rgb = av1_to_rgb(&color);
+    let alpha = alpha.map(|alpha| av1_plane(&alpha, PlanarImageComponent::Y, w, h).into_iter().map(|sample| av1_normalize(&alpha, sample, false)).collect::<Vec<f32>>());
+    let mut samples: Vec<f32> = Vec::with_capacity(rgb.len() * 4);
+    for (i, pixel) in rgb.into_iter().enumerate() {
+        match &alpha {
+            Some(alpha) => {
+                // Undo the premultiplication, the pixels of `image` are not premultiplied
+                let a = alpha[i];
+                let pixel = if avif.premultiplied_alpha && a > 0.0 { pixel.map(|sample| (sample / a).min(1.0)) } else { pixel };
+                samples.extend(pixel);
+                samples.push(a);
+            }
+            None => samples.extend(pixel),
+        }
+    }
+
+    let high_bit_depth = color.bits_per_component().is_some_and(|bits| bits.0 > 8);
+    let image = match (high_bit_depth, alpha.is_some()) {
+        (false, false) => ImageBuffer::from_raw(w, h, to_samples(&samples, u8::MAX)).map(DynamicImage::ImageRgb8),
+        (false, true) => ImageBuffer::from_raw(w, h, to_samples(&samples, u8::MAX)).map(DynamicImage::ImageRgba8),
+        (true, false) => ImageBuffer::from_raw(w, h, to_samples(&samples, u16::MAX)).map(DynamicImage::ImageRgb16),
+        (true, true) => ImageBuffer::from_raw(w, h, to_samples(&samples, u16::MAX)).map(DynamicImage::ImageRgba16),
+    };
+    image.ok_or("Invalid AVIF image. (The pixel data does not match the dimension)".to_string())
+}
+
+/// Decode a single AV1 frame (the color or the alpha item of an AVIF image)
+fn decode_av1(data: &[u8]) -> Result<Picture, String> {
+    // A still image has only one frame, do not wait for more
+    let mut settings = Av1Settings::new();
+    settings.set_max_frame_delay(1);
+
+    let mut decoder = Av1Decoder::with_settings(&settings).map_err(|err| format!("Failed to decode AVIF ({err})"))?;
+    decoder.send_data(data.to_vec(), None, None, None).map_err(|err| format!("Failed to decode AVIF ({err})"))?;
+    decoder.get_picture().map_err(|err| format!("Failed to decode AVIF ({err})"))
+}
+
+/// The samples of a plane (`w` x `h`) of the decoded frame, 10/12-bit samples are stored as native endian `u16`
+fn av1_plane(picture: &Picture, component: PlanarImageComponent, w: u32, h: u32) -> Vec<u16> {
+    let plane = picture.plane(component);
+    let stride = picture.stride(component) as usize;
+    let high_bit_depth = picture.bit_depth() > 8;
+
+    let mut samples = Vec::with_capacity((w * h) as usize);
+    for y in 0..h as usize {
+        let row = &plane[y * stride..];
+        for x in 0..w as usize {
+            samples.push(if high_bit_depth { u16::from_ne_bytes([row[x * 2], row[x * 2 + 1]]) } else { row[x] as u16 });
+        }
+    }
+    samples
+}
+
+/// Map a sample to 0-1 (luma and alpha) or -0.5-0.5 (chroma) according to the color range
+fn av1_normalize(picture: &Picture, sample: u16, chroma: bool) -> f32 {
+    let bits = picture.bits_per_component().map_or(8, |bits| bits.0) as i32;
+    let scale = (1 << (bits - 8)) as f32;
+    let sample = sample as f32;
+    match (picture.color_range(), chroma) {
+        (pixel::YUVRange::Full, false) => sample / ((1 << bits) - 1) as f32,
+        (pixel::YUVRange::Full, true) => (sample - (1 << (bits - 1)) as f32) / ((1 << bits) - 1) as f32,
+        (pixel::YUVRange::Limited, false) => (sample - 16.0 * scale) / (219.0 * scale),
+        (pixel::YUVRange::Limited, true) => (sample - 128.0 * scale) / (224.0 * scale),
     }
 }
 
-fn guess_out_format(format: &str) -> Option<ImageOutputFormat> {
+/// Convert the YUV planes to RGB (0-1) with the matrix coefficients of the frame (BT.601 if unspecified),
+/// subsampled chroma is taken from the nearest sample
+fn av1_to_rgb(picture: &Picture) -> Vec<[f32; 3]> {
+    let (w, h) = (picture.width(), picture.height());
+    let luma = av1_plane(picture, PlanarImageComponent::Y, w, h);
+    let (ssx, ssy) = match picture.pixel_layout() {
+        PixelLayout::I400 => return luma.into_iter().map(|y| [av1_normalize(picture, y, false).clamp(0.0, 1.0); 3]).collect(),
+        PixelLayout::I420 => (1, 1),
+        PixelLayout::I422 => (1, 0),
+        PixelLayout::I444 => (0, 0),
+    };
+    let (cw, ch) = ((w + ssx) >> ssx, (h + ssy) >> ssy);
+    let u = av1_plane(picture, PlanarImageComponent::U, cw, ch);
+    let v = av1_plane(picture, PlanarImageComponent::V, cw, ch);
+
+    // (Kr, Kb) of the matrix, `None` for the identity matrix (GBR)
+    let coefficients = match picture.matrix_coefficients() {
+        pixel::MatrixCoefficients::Identity => None,
+        pixel::MatrixCoefficients::BT709 => Some((0.2126, 0.0722)),
+        pixel::MatrixCoefficients::BT470M => Some((0.30, 0.11)),
+        pixel::MatrixCoefficients::ST240M => Some((0.212, 0.087)),
+        pixel::MatrixCoefficients::BT2020NonConstantLuminance | pixel::MatrixCoefficients::BT2020ConstantLuminance => Some((0.2627, 0.0593)),
+        _ => Some((0.299, 0.114)),
+    };
+
+    let mut rgb = Vec::with_capacity((w * h) as usize);
+    for y in 0..h {
+        for x in 0..w {
+            let chroma = ((y >> ssy) * cw + (x >> ssx)) as usize;
+            let (l, cb, cr) = (luma[(y * w + x) as usize], u[chroma], v[chroma]);
+            let pixel = match coefficients {
+                // GBR: Y is green, U is blue and V is red
+                None => [av1_normalize(picture, cr, false), av1_normalize(picture, l, false), av1_normalize(picture, cb, false)],
+                Some((kr, kb)) => {
+                    let (l, cb, cr) = (av1_normalize(picture, l, false), av1_normalize(picture, cb, true), av1_normalize(picture, cr, true));
+                    let r = l + 2.0 * (1.0 - kr) * cr;
+                    let b = l + 2.0 * (1.0 - kb) * cb;
+                    let g = (l - kr * r - kb * b) / (1.0 - kr - kb);
+                    [r, g, b]
+                }
+            };
+            rgb.push(pixel.map(|sample| sample.clamp(0.0, 1.0)));
+        }
+    }
+    rgb
+}
+
+/// Scale the samples (0-1) to the integer samples with the maximum value `max`
+fn to_samples<T: TryFrom<u32> + Into<u32> + Default>(samples: &[f32], max: T) -> Vec<T> {
+    let max = max.into() as f32;
+    samples.iter().map(|sample| T::try_from((sample * max).round() as u32).unwrap_or_default()).collect()
+}
+
+fn guess_out_format(format: &str, quality: u8) -> Option<ImageOutputFormat> {
     match format {
         "bmp" => Some(ImageOutputFormat::Bmp),
         "gif" => Some(ImageOutputFormat::Gif),
         "ico" => Some(ImageOutputFormat::Ico),
-        "jpg" | "jpeg" => Some(ImageOutputFormat::Jpeg(100)),
+        "jpg" | "jpeg" => Some(ImageOutputFormat::Jpeg(quality)),
         "png" => Some(ImageOutputFormat::Png),
         "tiff" => Some(ImageOutputFormat::Tiff),
+        "qoi" => Some(ImageOutputFormat::Qoi),
+        "tga" => Some(ImageOutputFormat::Tga),
+        "pbm" => Some(ImageOutputFormat::Pnm(PnmSubtype::Bitmap(SampleEncoding::Binary))),
+        "pgm" => Some(ImageOutputFormat::Pnm(PnmSubtype::Graymap(SampleEncoding::Binary))),
+        "ppm" | "pnm" => Some(ImageOutputFormat::Pnm(PnmSubtype::Pixmap(SampleEncoding::Binary))),
+        "pam" => Some(ImageOutputFormat::Pnm(PnmSubtype::ArbitraryMap)),
+        "exr" => Some(ImageOutputFormat::OpenExr),
+        "ff" => Some(ImageOutputFormat::Farbfeld),
         _ => None
     }
 }
 
-pub fn to_image(dyn_image: DynamicImage, wh: Option<(u32, u32)>, format: &str) -> Result<Vec<u8>, String> {
-    match guess_out_format(format) {
-        Some(out_format) => {
-            let final_image = match wh {
-                Some((w, h)) => dyn_image.resize(w, h, FilterType::Nearest),
-                None => dyn_image
-            };
+/// Parse the resampling filter (the same names as sharp's `kernel`, case insensitive)
+pub fn parse_filter(filter: &str) -> Result<FilterType, String> {
+    match filter.to_lowercase().as_str() {
+        "nearest" => Ok(FilterType::Nearest),
+        "triangle" => Ok(FilterType::Triangle),
+        "catmull-rom" | "catmullrom" => Ok(FilterType::CatmullRom),
+        "gaussian" => Ok(FilterType::Gaussian),
+        "lanczos3" => Ok(FilterType::Lanczos3),
+        _ => Err(format!("Invalid filter. (Expect one of: nearest, triangle, catmull-rom, gaussian, lanczos3, Got: {filter})"))
+    }
+}
 
-            let mut png_buffer = vec![];
-            match final_image.write_to(&mut Cursor::new(&mut png_buffer), out_format) {
-                Ok(_) => Ok(png_buffer),
-                Err(err) => Err(format!("{err}"))
-            }
+/// How the image is fitted into the target size, see https://sharp.pixelplumbing.com/api-resize
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Fit {
+    /// Preserve aspect ratio, fit within the target size and pad the rest (transparent)
+    Contain,
+    /// Preserve aspect ratio, cover the target size and crop the overflow according to the gravity
+    Cover,
+    /// Ignore the aspect ratio and stretch to the exact target size
+    Fill,
+    /// Preserve aspect ratio, as large as possible while both dimensions are less than or equal to the target
+    Inside,
+    /// Preserve aspect ratio, as small as possible while both dimensions are greater than or equal to the target
+    Outside,
+}
+
+impl Fit {
+    pub fn parse(fit: &str) -> Result<Fit, String> {
+        match fit.to_lowercase().as_str() {
+            "contain" => Ok(Fit::Contain),
+            "cover" => Ok(Fit::Cover),
+            "fill" => Ok(Fit::Fill),
+            "inside" => Ok(Fit::Inside),
+            "outside" => Ok(Fit::Outside),
+            _ => Err(format!("Invalid fit. (Expect one of: contain, cover, fill, inside, outside, Got: {fit})"))
+        }
+    }
+}
+
+/// Which part of the image is kept when cropping (`cover`) or where it is placed when padding (`contain`)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Gravity {
+    Center,
+    North,
+    NorthEast,
+    East,
+    SouthEast,
+    South,
+    SouthWest,
+    West,
+    NorthWest,
+}
+
+impl Gravity {
+    pub fn parse(gravity: &str) -> Result<Gravity, String> {
+        match gravity.to_lowercase().as_str() {
+            "center" | "centre" => Ok(Gravity::Center),
+            "north" => Ok(Gravity::North),
+            "northeast" => Ok(Gravity::NorthEast),
+            "east" => Ok(Gravity::East),
+            "southeast" => Ok(Gravity::SouthEast),
+            "south" => Ok(Gravity::South),
+            "southwest" => Ok(Gravity::SouthWest),
+            "west" => Ok(Gravity::West),
+            "northwest" => Ok(Gravity::NorthWest),
+            _ => Err(format!("Invalid gravity. (Expect one of: center, north, northeast, east, southeast, south, southwest, west, northwest, Got: {gravity})"))
         }
-        None => Err(format!("Invalid format. (Expect one of: bmp, gif, ico, jpg/jpeg, png, tiff, Got: {format})"))
     }
-}
\ No newline at end of file
+
+    /// The offset of the kept (or placed) area, given the free space in each direction
+    fn offset(&self, free_w: u32, free_h: u32) -> (u32, u32) {
+        let x = match self {
+            Gravity::West | Gravity::NorthWest | Gravity::SouthWest => 0,
+            Gravity::East | Gravity::NorthEast | Gravity::SouthEast => free_w,
+            _ => free_w / 2,
+        };
+        let y = match self {
+            Gravity::North | Gravity::NorthWest | Gravity::NorthEast => 0,
+            Gravity::South | Gravity::SouthWest | Gravity::SouthEast => free_h,
+            _ => free_h / 2,
+        };
+        (x, y)
+    }
+}
+
+pub struct ResizeOptions {
+    /// Target (width, height)
+    pub size: (u32, u32),
+    pub filter: FilterType,
+    pub fit: Fit,
+    pub gravity: Gravity,
+}
+
+/// Scale (w, h) proportionally so that both dimensions are greater than or equal to the target
+fn cover_size((w, h): (u32, u32), (tw, th): (u32, u32)) -> (u32, u32) {
+    let ratio = f64::max(tw as f64 / w as f64, th as f64 / h as f64);
+    (((w as f64 * ratio).round() as u32).max(tw), ((h as f64 * ratio).round() as u32).max(th))
+}
+
+/// Resize the image according to the fit mode
+pub fn resize(dyn_image: DynamicImage, options: &ResizeOptions) -> DynamicImage {
+    let (w, h) = options.size;
+    let filter = options.filter;
+
+    match options.fit {
+        Fit::Fill => dyn_image.resize_exact(w, h, filter),
+        Fit::Inside => dyn_image.resize(w, h, filter),
+        Fit::Outside => {
+            let (nw, nh) = cover_size(dyn_image.dimensions(), (w, h));
+            dyn_image.resize_exact(nw, nh, filter)
+        }
+        Fit::Cover => {
+            let (nw, nh) = cover_size(dyn_image.dimensions(), (w, h));
+            let resized = dyn_image.resize_exact(nw, nh, filter);
+            let (x, y) = options.gravity.offset(nw - w, nh - h);
+            resized.crop_imm(x, y, w, h)
+        }
+        Fit::Contain => {
+            let resized = dyn_image.resize(w, h, filter);
+            let (x, y) = options.gravity.offset(w - resized.width(), h - resized.height());
+            let mut canvas = DynamicImage::new_rgba8(w, h);
+            imageops::overlay(&mut canvas, &resized, x as i64, y as i64);
+            canvas
+        }
+    }
+}
+
+/// Parse the PNG compression level
+pub fn parse_png_compression(compression: &str) -> Result<CompressionType, String> {
+    match compression.to_lowercase().as_str() {
+        "fast" => Ok(CompressionType::Fast),
+        "default" => Ok(CompressionType::Default),
+        "best" => Ok(CompressionType::Best),
+        _ => Err(format!("Invalid PNG compression. (Expect one of: fast, default, best, Got: {compression})"))
+    }
+}
+
+/// Parse the PNG filter
+pub fn parse_png_filter(filter: &str) -> Result<PngFilterType, String> {
+    match filter.to_lowercase().as_str() {
+        "none" => Ok(PngFilterType::NoFilter),
+        "sub" => Ok(PngFilterType::Sub),
+        "up" => Ok(PngFilterType::Up),
+        "avg" => Ok(PngFilterType::Avg),
+        "paeth" => Ok(PngFilterType::Paeth),
+        "adaptive" => Ok(PngFilterType::Adaptive),
+        _ => Err(format!("Invalid PNG filter. (Expect one of: none, sub, up, avg, paeth, adaptive, Got: {filter})"))
+    }
+}
+
+/// Compression of the TIFF output
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TiffCompression {
+    None,
+    Lzw,
+    Deflate,
+    Packbits,
+}
+
+impl TiffCompression {
+    pub fn parse(compression: &str) -> Result<TiffCompression, String> {
+        match compression.to_lowercase().as_str() {
+            "none" => Ok(TiffCompression::None),
+            "lzw" => Ok(TiffCompression::Lzw),
+            "deflate" => Ok(TiffCompression::Deflate),
+            "packbits" => Ok(TiffCompression::Packbits),
+            _ => Err(format!("Invalid TIFF compression. (Expect one of: none, lzw, deflate, packbits, Got: {compression})"))
+        }
+    }
+}
+
+/// Encoder settings of the output formats
+pub struct EncodeOptions {
+    /// Quality (1-100) of the lossy formats
+    pub quality: u8,
+    /// Whether to write a progressive JPEG
+    pub progressive: bool,
+    /// Whether to write a lossless WebP (`quality` is ignored)
+    pub lossless: bool,
+    /// AVIF encoding speed (1-10)
+    pub speed: u8,
+    pub png_compression: CompressionType,
+    pub png_filter: PngFilterType,
+    pub tiff_compression: TiffCompression,
+    /// How HDR images are mapped to 8/16-bit targets
+    pub tone_map: ToneMap,
+    /// Exposure adjustment in stops applied before tone mapping
+    pub exposure: f32,
+}
+
+impl Default for EncodeOptions {
+    fn default() -> Self {
+        Self {
+            quality: DEFAULT_QUALITY,
+            progressive: false,
+            lossless: false,
+            speed: DEFAULT_SPEED,
+            png_compression: CompressionType::Fast,
+            png_filter: PngFilterType::Adaptive,
+            tiff_compression: TiffCompression::None,
+            tone_map: ToneMap::Reinhard,
+            exposure: 0.0,
+        }
+    }
+}
+
+/// How the linear, unbounded samples of HDR (floating point) images are mapped to the displayable range
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ToneMap {
+    /// `x / (1 + x)`, keeps the highlights but looks flat
+    Reinhard,
+    /// The filmic curve of ACES (Narkowicz's approximation), more contrast
+    Aces,
+    /// Clip everything above 1.0
+    Clamp,
+}
+
+impl ToneMap {
+    pub fn parse(tone_map: &str) -> Result<ToneMap, String> {
+        match tone_map.to_lowercase().as_str() {
+            "reinhard" => Ok(ToneMap::Reinhard),
+            "aces" => Ok(ToneMap::Aces),
+            "clamp" => Ok(ToneMap::Clamp),
+            _ => Err(format!("Invalid tone map. (Expect one of: reinhard, aces, clamp, Got: {tone_map})"))
+        }
+    }
+
+    fn apply(&self, x: f32) -> f32 {
+        let x = x.max(0.0);
+        let mapped = match self {
+            ToneMap::Reinhard => x / (1.0 + x),
+            ToneMap::Aces => (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14),
+            ToneMap::Clamp => x,
+        };
+        mapped.min(1.0)
+    }
+}
+
+/// The sRGB transfer function (linear -> display), both in [0, 1]
+fn linear_to_srgb(x: f32) -> f32 {
+    if x <= 0.0031308 { x * 12.92 } else { 1.055 * x.powf(1.0 / 2.4) - 0.055 }
+}
+
+/// The inverse sRGB transfer function (display -> linear)
+fn srgb_to_linear(x: f32) -> f32 {
+    if x <= 0.04045 { x / 12.92 } else { ((x + 0.055) / 1.055).powf(2.4) }
+}
+
+/// Apply `f` to the color (not alpha) samples of a floating point image
+fn map_color(dyn_image: DynamicImage, f: impl Fn(f32) -> f32) -> DynamicImage {
+    match dyn_image {
+        DynamicImage::ImageRgba32F(mut img) => {
+            img.pixels_mut().for_each(|pixel| pixel.0[..3].iter_mut().for_each(|sample| *sample = f(*sample)));
+            DynamicImage::ImageRgba32F(img)
+        }
+        DynamicImage::ImageRgb32F(mut img) => {
+            img.pixels_mut().for_each(|pixel| pixel.0.iter_mut().for_each(|sample| *sample = f(*sample)));
+            DynamicImage::ImageRgb32F(img)
+        }
+        other => other,
+    }
+}
+
+fn is_float(dyn_image: &DynamicImage) -> bool {
+    matches!(dyn_image, DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_))
+}
+
+/// Convert the image to a color type accepted by the encoder of the target format:
+/// HDR images written to other formats are tone mapped, and 8/16-bit images written to HDR formats are linearized
+fn prepare(dyn_image: DynamicImage, format: &str, encode_options: &EncodeOptions) -> DynamicImage {
+    let has_alpha = dyn_image.color().has_alpha();
+
+    if matches!(format, "exr" | "hdr") {
+        if is_float(&dyn_image) {
+            return dyn_image;
+        }
+        let float_image = if has_alpha { DynamicImage::ImageRgba32F(dyn_image.to_rgba32f()) } else { DynamicImage::ImageRgb32F(dyn_image.to_rgb32f()) };
+        return map_color(float_image, srgb_to_linear);
+    }
+
+    let dyn_image = match is_float(&dyn_image) {
+        true => {
+            let scale = encode_options.exposure.exp2();
+            map_color(dyn_image, |x| linear_to_srgb(encode_options.tone_map.apply(x * scale)))
+        }
+        false => dyn_image,
+    };
+    match (format, &dyn_image) {
+        ("ff", _) => DynamicImage::ImageRgba16(dyn_image.to_rgba16()),
+        ("pbm" | "pgm", _) => DynamicImage::ImageLuma8(dyn_image.to_luma8()),
+        ("ppm" | "pnm", _) => DynamicImage::ImageRgb8(dyn_image.to_rgb8()),
+        // PNG and TIFF keep 16 bits per sample of the tone mapped HDR images
+        ("png" | "tiff", DynamicImage::ImageRgba32F(_)) => DynamicImage::ImageRgba16(dyn_image.to_rgba16()),
+        ("png" | "tiff", DynamicImage::ImageRgb32F(_)) => DynamicImage::ImageRgb16(dyn_image.to_rgb16()),
+        ("qoi", _) | (_, DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_)) if has_alpha => DynamicImage::ImageRgba8(dyn_image.to_rgba8()),
+        ("qoi", _) | (_, DynamicImage::ImageRgb32F(_)) => DynamicImage::ImageRgb8(dyn_image.to_rgb8()),
+        // TGA and PAM only accept 8-bit samples
+        ("tga" | "pam", DynamicImage::ImageLuma16(_)) => DynamicImage::ImageLuma8(dyn_image.to_luma8()),
+        ("tga" | "pam", DynamicImage::ImageLumaA16(_)) => DynamicImage::ImageLumaA8(dyn_image.to_luma_alpha8()),
+        ("tga" | "pam", DynamicImage::ImageRgb16(_)) => DynamicImage::ImageRgb8(dyn_image.to_rgb8()),
+        ("tga" | "pam", DynamicImage::ImageRgba16(_)) => DynamicImage::ImageRgba8(dyn_image.to_rgba8()),
+        _ => dyn_image,
+    }
+}
+
+/// Radiance HDR is not an `ImageOutputFormat`, its encoder takes RGB floating point pixels
+fn encode_hdr(dyn_image: &DynamicImage) -> Result<Vec<u8>, String> {
+    let pixels: Vec<image::Rgb<f32>> = dyn_image.to_rgb32f().pixels().copied().collect();
+    let mut buffer = vec![];
+    match HdrEncoder::new(&mut buffer).encode(&pixels, dyn_image.width() as usize, dyn_image.height() as usize) {
+        Ok(_) => Ok(buffer),
+        Err(err) => Err(format!("{err}"))
+    }
+}
+
+/// PBM is written by hand: the `PnmEncoder` of `image` 0.24 inverts its sample range check and rejects bitmaps whose
+/// samples are all 0 or 1 (e.g. an all black image). Pixels darker than middle gray become black (1 bits), and each row
+/// is padded to a whole byte
+fn encode_pbm(dyn_image: &DynamicImage) -> Vec<u8> {
+    let gray = dyn_image.to_luma8();
+    let mut buffer = format!("P4\n{} {}\n", gray.width(), gray.height()).into_bytes();
+    for row in gray.rows() {
+        let black: Vec<bool> = row.map(|pixel| pixel[0] < 128).collect();
+        for bits in black.chunks(8) {
+            buffer.push(bits.iter().enumerate().fold(0u8, |byte, (i, black)| byte | ((*black as u8) << (7 - i))));
+        }
+    }
+    buffer
+}
+
+/// Whether images can be written in this format (some formats, e.g. DDS, can only be read)
+pub fn is_writable(format: &str) -> bool {
+    guess_out_format(format, DEFAULT_QUALITY).is_some() || matches!(format, "webp" | "avif" | "hdr")
+}
+
+/// The encoder of `image` only writes baseline JPEG, so progressive JPEG is written by `jpeg-encoder`
+fn encode_progressive_jpeg(dyn_image: &DynamicImage, quality: u8) -> Result<Vec<u8>, String> {
+    let (w, h) = match (u16::try_from(dyn_image.width()), u16::try_from(dyn_image.height())) {
+        (Ok(w), Ok(h)) => (w, h),
+        _ => return Err("Invalid size. (The width and height of a JPEG image must not exceed 65535)".to_string()),
+    };
+
+    let mut buffer = vec![];
+    let mut encoder = jpeg_encoder::Encoder::new(&mut buffer, quality);
+    encoder.set_progressive(true);
+    let result = match dyn_image {
+        DynamicImage::ImageLuma8(gray) => encoder.encode(gray.as_raw(), w, h, jpeg_encoder::ColorType::Luma),
+        _ => encoder.encode(dyn_image.to_rgb8().as_raw(), w, h, jpeg_encoder::ColorType::Rgb),
+    };
+    result.map_err(|err| format!("{err}"))?;
+    Ok(buffer)
+}
+
+/// The encoder of `image` only writes uncompressed TIFF, so TIFF is written by `tiff` directly
+fn encode_tiff(dyn_image: &DynamicImage, compression: TiffCompression) -> Result<Vec<u8>, String> {
+    match compression {
+        TiffCompression::None => write_tiff(dyn_image, Uncompressed),
+        TiffCompression::Lzw => write_tiff(dyn_image, Lzw),
+        TiffCompression::Deflate => write_tiff(dyn_image, Deflate::default()),
+        TiffCompression::Packbits => write_tiff(dyn_image, Packbits),
+    }
+}
+
+fn write_tiff<D: Compression>(dyn_image: &DynamicImage, compression: D) -> Result<Vec<u8>, String> {
+    let mut buffer = Cursor::new(vec![]);
+    let mut encoder = tiff::encoder::TiffEncoder::new(&mut buffer).map_err(|err| format!("{err}"))?;
+    let (w, h) = dyn_image.dimensions();
+
+    let result = match dyn_image {
+        DynamicImage::ImageLuma8(img) => encoder.write_image_with_compression::<Gray8, D>(w, h, compression, img.as_raw()),
+        DynamicImage::ImageRgb8(img) => encoder.write_image_with_compression::<RGB8, D>(w, h, compression, img.as_raw()),
+        DynamicImage::ImageRgba8(img) => encoder.write_image_with_compression::<RGBA8, D>(w, h, compression, img.as_raw()),
+        DynamicImage::ImageLuma16(img) => encoder.write_image_with_compression::<Gray16, D>(w, h, compression, img.as_raw()),
+        DynamicImage::ImageRgb16(img) => encoder.write_image_with_compression::<RGB16, D>(w, h, compression, img.as_raw()),
+        DynamicImage::ImageRgba16(img) => encoder.write_image_with_compression::<RGBA16, D>(w, h, compression, img.as_raw()),
+        // Gray with alpha and floating point images are not supported by the encoder
+        _ => encoder.write_image_with_compression::<RGBA8, D>(w, h, compression, dyn_image.to_rgba8().as_raw()),
+    };
+    result.map_err(|err| format!("{err}"))?;
+    Ok(buffer.into_inner())
+}
+
+/// WebP is written by libwebp (`image` 0.24 can only decode it), which accepts 8-bit RGB(A) only
+fn encode_webp(dyn_image: &DynamicImage, encode_options: &EncodeOptions) -> Result<Vec<u8>, String> {
+    let (w, h) = dyn_image.dimensions();
+    let has_alpha = dyn_image.color().has_alpha();
+    let pixels = if has_alpha { dyn_image.to_rgba8().into_raw() } else { dyn_image.to_rgb8().into_raw() };
+    let encoder = if has_alpha { webp::Encoder::from_rgba(&pixels, w, h) } else { webp::Encoder::from_rgb(&pixels, w, h) };
+
+    match encoder.encode_simple(encode_options.lossless, encode_options.quality as f32) {
+        Ok(memory) => Ok(memory.to_vec()),
+        Err(err) => Err(format!("Failed to encode WebP ({err:?})"))
+    }
+}
+
+/// AVIF is written by ravif (rav1e), the alpha channel is only encoded if the image has one
+fn encode_avif(dyn_image: &DynamicImage, encode_options: &EncodeOptions) -> Result<Vec<u8>, String> {
+    let (w, h) = (dyn_image.width() as usize, dyn_image.height() as usize);
+    let encoder = ravif::Encoder::new()
+        .with_quality(encode_options.quality as f32)
+        .with_speed(encode_options.speed);
+
+    let result = if dyn_image.color().has_alpha() {
+        let pixels: Vec<ravif::RGBA8> = dyn_image.to_rgba8().pixels().map(|pixel| ravif::RGBA8::new(pixel[0], pixel[1], pixel[2], pixel[3])).collect();
+        encoder.encode_rgba(Img::new(&pixels[..], w, h))
+    } else {
+        let pixels: Vec<ravif::RGB8> = dyn_image.to_rgb8().pixels().map(|pixel| ravif::RGB8::new(pixel[0], pixel[1], pixel[2])).collect();
+        encoder.encode_rgb(Img::new(&pixels[..], w, h))
+    };
+    match result {
+        Ok(encoded) => Ok(encoded.avif_file),
+        Err(err) => Err(format!("Failed to encode AVIF ({err})"))
+    }
+}
+
+pub fn to_image(dyn_image: DynamicImage, resize_options: Option<ResizeOptions>, format: &str, encode_options: &EncodeOptions) -> Result<Vec<u8>, String> {
+    // WebP, AVIF and HDR are not `ImageOutputFormat`s (without the encoder features of `image`)
+    let out_format = guess_out_format(format, encode_options.quality);
+    if !is_writable(format) {
+        return Err(format!("Invalid format. (Expect one of: avif, bmp, exr, ff, gif, hdr, ico, jpg/jpeg, pam, pbm, pgm, png, ppm/pnm, qoi, tga, tiff, webp, Got: {format})"));
+    }
+
+    let final_image = match resize_options {
+        Some(resize_options) => resize(dyn_image, &resize_options),
+        None => dyn_image
+    };
+    let final_image = prepare(final_image, format, encode_options);
+
+    let mut png_buffer = vec![];
+    let result = match out_format {
+        None if format == "webp" => return encode_webp(&final_image, encode_options),
+        None if format == "hdr" => return encode_hdr(&final_image),
+        None => return encode_avif(&final_image, encode_options),
+        Some(ImageOutputFormat::Jpeg(quality)) if encode_options.progressive => return encode_progressive_jpeg(&final_image, quality),
+        Some(ImageOutputFormat::Tiff) => return encode_tiff(&final_image, encode_options.tiff_compression),
+        Some(ImageOutputFormat::Pnm(PnmSubtype::Bitmap(_))) => return Ok(encode_pbm(&final_image)),
+        Some(ImageOutputFormat::Png) => PngEncoder::new_with_quality(&mut png_buffer, encode_options.png_compression, encode_options.png_filter)
+            .write_image(final_image.as_bytes(), final_image.width(), final_image.height(), final_image.color()),
+        Some(out_format) => final_image.write_to(&mut Cursor::new(&mut png_buffer), out_format),
+    };
+    match result {
+        Ok(_) => Ok(png_buffer),
+        Err(err) => Err(format!("{err}"))
+    }
+}
@@ -1,17 +1,31 @@
 use std::env::current_dir;
 use clap::Parser;
+use crate::codec::{CodecImpl, CodecOptions};
 use crate::commands::{Cli, Commands};
+use crate::crypt::{CryptImpl, CryptOptions};
+use crate::dedupe::{DedupeImpl, DedupeOptions};
 use crate::doc::DocImpl;
 use crate::archive::{PackImpl, UnpackImpl};
-use crate::hash::HashImpl;
-use crate::image::ImageImpl;
+use crate::diff_dir::{DiffDirImpl, DiffDirOptions};
+use crate::hash::{HashImpl, HashOptions};
+use crate::iconv::{IconvImpl, IconvOptions};
+use crate::image::{ImageImpl, ImageOptions};
+use crate::password::{PasswordImpl, PasswordOptions};
 use crate::serve::ServeImpl;
+use crate::sri::{SriImpl, SriOptions};
 
 mod archive;
+mod codec;
 mod commands;
+mod crypt;
+mod dedupe;
+mod diff_dir;
 mod doc;
 mod hash;
+mod iconv;
+mod password;
 mod serve;
+mod sri;
 mod fontmin;
 mod image;
 
@@ -22,18 +36,50 @@ fn main() {
             Commands::Doc => {
                 DocImpl::handle();
             }
-            Commands::Hash { source, filemode, algorithm } => {
-                HashImpl::handle(source, filemode, algorithm);
+            Commands::Crypt { source, output, decrypt, algorithm, password, password_file, key, key_file, key_encoding, force } => {
+                CryptImpl::handle(CryptOptions { source, output, decrypt, algorithm, password, password_file, key, key_file, key_encoding, force });
             }
-            Commands::Image { source, format, size } => {
-                ImageImpl::handle(source, format, size);
+            Commands::Decode { source, filemode, encoding, output } => {
+                CodecImpl::handle(CodecOptions { source, filemode, encoding, output, no_padding: false, decode: true });
+            }
+            Commands::Dedupe { sources, algorithm, action, dry_run, min_size, jobs } => {
+                DedupeImpl::handle(DedupeOptions { sources, algorithm, action, dry_run, min_size, jobs });
+            }
+            Commands::DiffDir { left, right, algorithm, json, jobs } => {
+                DiffDirImpl::handle(DiffDirOptions { left, right, algorithm, json, jobs });
+            }
+            Commands::Encode { source, filemode, encoding, output, no_padding } => {
+                CodecImpl::handle(CodecOptions { source, filemode, encoding, output, no_padding, decode: false });
+            }
+            Commands::Hash {
+                sources, filemode, algorithm, output, check, json,
+                blake3_key, blake3_context, hmac_key, hmac_key_file, hmac_key_encoding, expect,
+                encoding, input_encoding, jobs, output_length, customization, git, archive,
+            } => {
+                HashImpl::handle(HashOptions {
+                    sources, filemode, algorithm, output, check, json,
+                    blake3_key, blake3_context, hmac_key, hmac_key_file, hmac_key_encoding, expect,
+                    encoding, input_encoding, jobs, output_length, customization, git, archive,
+                });
+            }
+            Commands::Iconv { sources, from, to, output, bom, extensions, dry_run } => {
+                IconvImpl::handle(IconvOptions { sources, from, to, output, bom, extensions, dry_run });
+            }
+            Commands::Image { source, format, size, output, filter, fit, gravity, quality, progressive, lossless, speed, png_compression, png_filter, tiff_compression, tone_map, exposure } => {
+                ImageImpl::handle(ImageOptions { source, format, size, output, filter, fit, gravity, quality, progressive, lossless, speed, png_compression, png_filter, tiff_compression, tone_map, exposure });
             }
             Commands::Pack { destination, sources } => {
                 PackImpl::handle(current_dir().unwrap(), destination, sources);
             }
+            Commands::Password { password, algorithm, verify, salt, iterations, memory, parallelism, cost, length } => {
+                PasswordImpl::handle(PasswordOptions { password, algorithm, verify, salt, iterations, memory, parallelism, cost, length });
+            }
             Commands::Serve { root, entry, port, mode } => {
                 ServeImpl::handle(root, entry, port, mode);
             }
+            Commands::Sri { root, entry, algorithm, write } => {
+                SriImpl::handle(SriOptions { root, entry, algorithm, write });
+            }
             Commands::Unpack { source, destination } => {
                 UnpackImpl::handle(source, destination);
             }
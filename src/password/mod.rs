@@ -0,0 +1,199 @@
+use std::io::{self, BufRead};
+use argon2::Argon2;
+use password_hash::rand_core::OsRng;
+use password_hash::{PasswordHash, PasswordHasher, SaltString};
+use pbkdf2::Pbkdf2;
+use scrypt::Scrypt;
+use crate::hash::from_hex;
+
+/// bcrypt 的默认成本 (2^12 轮)
+const BCRYPT_COST: u32 = 12;
+/// scrypt 的默认 log2(N), r 固定为 8
+const SCRYPT_LOG_N: u8 = 17;
+const SCRYPT_R: u32 = 8;
+/// PBKDF2-HMAC-SHA256 的默认迭代次数 (OWASP 推荐值)
+const PBKDF2_ITERATIONS: u32 = 600_000;
+/// Argon2 的默认参数 (OWASP 推荐值: 19 MiB, 2 轮, 1 线程)
+const ARGON2_MEMORY: u32 = 19 * 1024;
+const ARGON2_ITERATIONS: u32 = 2;
+/// 派生结果的默认字节长度
+const OUTPUT_LENGTH: usize = 32;
+
+/// Arguments of `Commands::Password`
+pub struct PasswordOptions {
+    pub password: String,
+    pub algorithm: String,
+    pub verify: Option<String>,
+    pub salt: Option<String>,
+    pub iterations: Option<u32>,
+    pub memory: Option<u32>,
+    pub parallelism: Option<u32>,
+    pub cost: Option<u32>,
+    pub length: Option<usize>,
+}
+
+/// Supported algorithms (case insensitive):
+/// - argon2id -- default; argon2i; argon2d (PHC string)
+/// - bcrypt ($2b$)
+/// - scrypt (PHC string)
+/// - pbkdf2 (PBKDF2-HMAC-SHA256); pbkdf2-sha512 (PHC string)
+pub struct PasswordImpl {}
+
+impl PasswordImpl {
+    /// 解析盐 (十六进制), 未指定时随机生成
+    fn salt(options: &PasswordOptions) -> Result<SaltString, String> {
+        match &options.salt {
+            Some(salt) => {
+                let bytes = from_hex(salt).ok_or("Salt must be hex encoded".to_string())?;
+                SaltString::encode_b64(&bytes).map_err(|err| format!("Invalid salt ({err})"))
+            }
+            None => Ok(SaltString::generate(&mut OsRng)),
+        }
+    }
+
+    /// 生成 bcrypt 哈希 (`$2b$`), 指定盐时必须为 16 字节
+    fn bcrypt(password: &[u8], options: &PasswordOptions) -> Result<String, String> {
+        let cost = options.cost.unwrap_or(BCRYPT_COST);
+        let parts = match &options.salt {
+            Some(salt) => {
+                let salt = from_hex(salt)
+                    .and_then(|bytes| <[u8; 16]>::try_from(bytes).ok())
+                    .ok_or("bcrypt salt must be 16 bytes (32 hex digits)".to_string())?;
+                bcrypt::hash_with_salt(password, cost, salt)
+            }
+            None => bcrypt::hash_with_result(password, cost),
+        };
+
+        parts.map(|parts| parts.format_for_version(bcrypt::Version::TwoB)).map_err(|err| format!("{err}"))
+    }
+
+    /// 生成 PHC 格式的哈希 (Argon2, scrypt, PBKDF2)
+    fn phc(password: &[u8], algorithm: &str, options: &PasswordOptions) -> Result<String, String> {
+        let salt = PasswordImpl::salt(options)?;
+        let length = options.length.unwrap_or(OUTPUT_LENGTH);
+
+        let hash = match algorithm {
+            "argon2id" | "argon2i" | "argon2d" => {
+                let variant = algorithm.parse::<argon2::Algorithm>().map_err(|err| format!("{err}"))?;
+                let params = argon2::Params::new(
+                    options.memory.unwrap_or(ARGON2_MEMORY),
+                    options.iterations.unwrap_or(ARGON2_ITERATIONS),
+                    options.parallelism.unwrap_or(1),
+                    Some(length),
+                ).map_err(|err| format!("Invalid Argon2 parameters ({err})"))?;
+                Argon2::new(variant, argon2::Version::V0x13, params).hash_password(password, &salt)
+            }
+            "scrypt" => {
+                let log_n = u8::try_from(options.cost.unwrap_or(SCRYPT_LOG_N as u32)).map_err(|err| format!("{err}"))?;
+                let params = scrypt::Params::new(log_n, SCRYPT_R, options.parallelism.unwrap_or(1), length)
+                    .map_err(|err| format!("Invalid scrypt parameters ({err})"))?;
+                Scrypt.hash_password_customized(password, None, None, params, &salt)
+            }
+            "pbkdf2" | "pbkdf2-sha256" | "pbkdf2-sha512" => {
+                let variant = if algorithm == "pbkdf2-sha512" { pbkdf2::Algorithm::Pbkdf2Sha512 } else { pbkdf2::Algorithm::Pbkdf2Sha256 };
+                let params = pbkdf2::Params { rounds: options.iterations.unwrap_or(PBKDF2_ITERATIONS), output_length: length };
+                Pbkdf2.hash_password_customized(password, Some(variant.ident()), None, params, &salt)
+            }
+            _ => return Err(format!("Unsupported algorithm '{algorithm}'")),
+        };
+
+        hash.map(|hash| hash.to_string()).map_err(|err| format!("{err}"))
+    }
+
+    /// 生成密码哈希
+    fn hash(password: &[u8], options: &PasswordOptions) -> Result<String, String> {
+        match options.algorithm.to_lowercase().as_str() {
+            "bcrypt" => PasswordImpl::bcrypt(password, options),
+            algorithm => PasswordImpl::phc(password, algorithm, options),
+        }
+    }
+
+    /// 校验密码与已有的哈希是否匹配, 算法及参数由哈希本身决定 (bcrypt 的 `$2a$`/`$2b$`/`$2y$` 或 PHC 字符串)
+    fn verify(password: &[u8], hash: &str) -> Result<bool, String> {
+        let hash = hash.trim();
+        if hash.starts_with("$2") {
+            return bcrypt::verify(password, hash).map_err(|err| format!("{err}"));
+        }
+
+        let parsed = PasswordHash::new(hash).map_err(|err| format!("Invalid password hash ({err})"))?;
+        match parsed.verify_password(&[&Argon2::default(), &Scrypt, &Pbkdf2], password) {
+            Ok(_) => Ok(true),
+            Err(password_hash::Error::Password) => Ok(false),
+            Err(err) => Err(format!("{err}")),
+        }
+    }
+
+    /// 读取密码, '-' 表示从标准输入读取一行 (避免密码出现在命令行历史中)
+    fn read_password(password: String) -> Result<Vec<u8>, String> {
+        if password != "-" {
+            return Ok(password.into_bytes());
+        }
+
+        let mut line = String::new();
+        io::stdin().lock().read_line(&mut line).map_err(|err| format!("{err}"))?;
+        Ok(line.trim_end_matches(['\r', '\n']).as_bytes().to_vec())
+    }
+
+    /// 处理 Command::Password 子命令
+    pub fn handle(options: PasswordOptions) {
+        match &options.verify {
+            // 校验时算法由哈希本身决定
+            Some(_) => println!("[Commands::Password] mode = 'verify'"),
+            None => println!("[Commands::Password] mode = 'hash', algorithm = '{}'", options.algorithm),
+        }
+
+        let result = PasswordImpl::read_password(options.password.clone()).and_then(|password| match &options.verify {
+            Some(hash) => match PasswordImpl::verify(&password, hash)? {
+                true => Ok("Password matches".to_string()),
+                false => Err("Password does not match".to_string()),
+            },
+            None => PasswordImpl::hash(&password, &options),
+        });
+
+        match result {
+            Ok(result) => println!("Ok: {result}"),
+            Err(err) => {
+                println!("Error: {err}");
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod unit_test {
+    use super::*;
+
+    fn options(algorithm: &str) -> PasswordOptions {
+        PasswordOptions {
+            password: String::new(),
+            algorithm: algorithm.to_string(),
+            verify: None,
+            salt: Some("73616c7473616c7473616c7473616c74".to_string()),
+            iterations: Some(1),
+            memory: Some(64),
+            parallelism: None,
+            cost: Some(4),
+            length: None,
+        }
+    }
+
+    #[test]
+    fn hash_verify_test() {
+        for algorithm in ["argon2id", "argon2i", "bcrypt", "scrypt", "pbkdf2", "pbkdf2-sha512"] {
+            let hash = PasswordImpl::hash(b"hunter2", &options(algorithm)).unwrap();
+            assert!(PasswordImpl::verify(b"hunter2", &hash).unwrap(), "{algorithm}");
+            assert!(!PasswordImpl::verify(b"hunter3", &hash).unwrap(), "{algorithm}");
+        }
+        assert!(PasswordImpl::hash(b"hunter2", &options("md5")).is_err());
+        assert!(PasswordImpl::verify(b"hunter2", "not a hash").is_err());
+    }
+
+    /// RFC 7914 中 PBKDF2-HMAC-SHA256 的测试向量 (P = "passwd", S = "salt", c = 1)
+    #[test]
+    fn pbkdf2_test() {
+        let options = PasswordOptions { salt: Some("73616c74".to_string()), length: Some(64), ..options("pbkdf2") };
+        let hash = PasswordImpl::hash(b"passwd", &options).unwrap();
+        assert_eq!(hash, "$pbkdf2-sha256$i=1,l=64$c2FsdA$VawEblbjCJ/sFpHCJUS2BflBhSFt3gRl5oudV8INrLxJypzM8Xm2RZkWZLOdd+8xfHG4RbHjC9UJESBB06GXgw");
+    }
+}
@@ -5,7 +5,6 @@ mod mixed;
 use std::env::current_dir;
 use std::net::{IpAddr, Ipv4Addr};
 use std::path::{PathBuf};
-use ipconfig::get_adapters;
 use rocket::{Config, catch, routes, Request, catchers};
 use rocket::http::ext::Normalize;
 use rocket::tokio::runtime::Runtime;
@@ -110,16 +109,29 @@ fn internal_error() -> &'static str {
 pub struct ServeImpl {}
 
 impl ServeImpl {
+    /// 获取本机所有网卡的ip地址 (Windows 通过 ipconfig, 其余平台通过 getifaddrs)
+    #[cfg(windows)]
+    fn get_adapter_ips() -> Vec<IpAddr> {
+        ipconfig::get_adapters().map_or(vec![], |adapters| {
+            adapters.iter().flat_map(|adapter| adapter.ip_addresses().to_vec()).collect()
+        })
+    }
+
+    #[cfg(not(windows))]
+    fn get_adapter_ips() -> Vec<IpAddr> {
+        if_addrs::get_if_addrs().map_or(vec![], |interfaces| {
+            interfaces.iter().map(|interface| interface.ip()).collect()
+        })
+    }
+
     /// 获取本机的所有局域网ip地址
     fn get_ips() -> Vec<String> {
         let mut ips = vec![format!("localhost"), format!("127.0.0.1")];
-        for adapter in get_adapters().unwrap() {
-            for ip_address in adapter.ip_addresses() {
-                if ip_address.is_ipv4() {
-                    let ip_str = ip_address.to_string();
-                    if ip_str.starts_with("192.") || ip_str.starts_with("172.") || ip_str.starts_with("10.") {
-                        ips.push(ip_str);
-                    }
+        for ip_address in ServeImpl::get_adapter_ips() {
+            if ip_address.is_ipv4() {
+                let ip_str = ip_address.to_string();
+                if ip_str.starts_with("192.") || ip_str.starts_with("172.") || ip_str.starts_with("10.") {
+                    ips.push(ip_str);
                 }
             }
         }
@@ -220,21 +232,10 @@ impl ServeImpl {
 #[cfg(test)]
 mod unit_test {
     use super::*;
-    use ipconfig::{get_adapters};
 
     #[test]
     fn ips() {
-        let mut ips = vec![format!("localhost"), format!("127.0.0.1")];
-        for adapter in get_adapters().unwrap() {
-            for ip_address in adapter.ip_addresses() {
-                if ip_address.is_ipv4() {
-                    let ip_str = ip_address.to_string();
-                    if ip_str.starts_with("192.") || ip_str.starts_with("172.") || ip_str.starts_with("10.") {
-                        ips.push(ip_str);
-                    }
-                }
-            }
-        }
+        let ips = ServeImpl::get_ips();
         println!("ips: {:?}", ips);
     }
 
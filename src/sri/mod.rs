@@ -0,0 +1,392 @@
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use crate::hash::{digest_file, DigestEncoding};
+
+/// Arguments of `Commands::Sri`
+pub struct SriOptions {
+    pub root: String,
+    pub entry: String,
+    pub algorithm: String,
+    pub write: bool,
+}
+
+/// HTML 标签中的一个属性
+struct Attribute {
+    /// 属性名 (小写)
+    name: String,
+    value: String,
+    /// 整个属性 (含属性名与值) 在文档中的范围
+    range: Range<usize>,
+}
+
+/// HTML 中的一个开始标签
+struct Tag {
+    /// 标签名 (小写)
+    name: String,
+    attributes: Vec<Attribute>,
+    /// 新属性的插入位置 (最后一个属性或标签名之后)
+    insert_at: usize,
+}
+
+impl Tag {
+    fn attribute(&self, name: &str) -> Option<&Attribute> {
+        self.attributes.iter().find(|attribute| attribute.name == name)
+    }
+}
+
+/// 扫描 HTML 中的所有开始标签 (跳过注释, 以及 `<script>`/`<style>` 的内容)
+fn scan_tags(html: &str) -> Vec<Tag> {
+    let bytes = html.as_bytes();
+    // 仅转换 ASCII 字符, 字节位置与 `html` 一致
+    let lower = html.to_ascii_lowercase();
+    let mut tags = vec![];
+    let mut pos = 0;
+
+    while let Some(offset) = html[pos..].find('<') {
+        pos += offset + 1;
+        if html[pos..].starts_with("!--") {
+            pos = html[pos..].find("-->").map_or(bytes.len(), |end| pos + end + 3);
+            continue;
+        }
+        if !bytes.get(pos).is_some_and(|byte| byte.is_ascii_alphabetic()) {
+            continue;
+        }
+
+        let start = pos;
+        while pos < bytes.len() && !bytes[pos].is_ascii_whitespace() && !b"/>".contains(&bytes[pos]) {
+            pos += 1;
+        }
+        let mut tag = Tag { name: html[start..pos].to_lowercase(), attributes: vec![], insert_at: pos };
+
+        loop {
+            while pos < bytes.len() && (bytes[pos].is_ascii_whitespace() || bytes[pos] == b'/') {
+                pos += 1;
+            }
+            if pos >= bytes.len() || bytes[pos] == b'>' {
+                break;
+            }
+
+            let name_start = pos;
+            while pos < bytes.len() && !bytes[pos].is_ascii_whitespace() && !b"/>=".contains(&bytes[pos]) {
+                pos += 1;
+            }
+            let name = html[name_start..pos].to_lowercase();
+
+            let mut lookahead = pos;
+            while lookahead < bytes.len() && bytes[lookahead].is_ascii_whitespace() {
+                lookahead += 1;
+            }
+            let mut value = String::new();
+            if bytes.get(lookahead) == Some(&b'=') {
+                pos = lookahead + 1;
+                while pos < bytes.len() && bytes[pos].is_ascii_whitespace() {
+                    pos += 1;
+                }
+                match bytes.get(pos) {
+                    Some(&quote) if quote == b'"' || quote == b'\'' => {
+                        let end = html[pos + 1..].find(quote as char).map_or(bytes.len(), |end| pos + 1 + end);
+                        value = html[pos + 1..end].to_string();
+                        pos = (end + 1).min(bytes.len());
+                    }
+                    _ => {
+                        // 无引号的值直接以 "/>" 结束时, '/' 属于自闭合标记而不是值 (e.g. `<script src=/a.js/>`)
+                        let value_start = pos;
+                        while pos < bytes.len() && !bytes[pos].is_ascii_whitespace() && bytes[pos] != b'>' && !html[pos..].starts_with("/>") {
+                            pos += 1;
+                        }
+                        value = html[value_start..pos].to_string();
+                    }
+                }
+            }
+
+            tag.attributes.push(Attribute { name, value, range: name_start..pos });
+            tag.insert_at = pos;
+        }
+
+        // `<script>` 与 `<style>` 的内容不是 HTML, 其中的 '<' 不应被当作标签
+        if tag.name == "script" || tag.name == "style" {
+            let close = format!("</{}", tag.name);
+            pos = lower[pos..].find(&close).map_or(bytes.len(), |end| pos + end);
+        }
+        tags.push(tag);
+    }
+
+    tags
+}
+
+/// 返回需要添加完整性校验的资源 URL: `<script src>` 与 `<link rel="stylesheet" href>`
+fn resource_url(tag: &Tag) -> Option<&str> {
+    match tag.name.as_str() {
+        "script" => tag.attribute("src").map(|src| src.value.as_str()),
+        "link" => {
+            let rel = tag.attribute("rel")?.value.to_lowercase();
+            if rel.split_ascii_whitespace().any(|rel| rel == "stylesheet") {
+                tag.attribute("href").map(|href| href.value.as_str())
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+/// 还原 URL 中的百分号编码
+fn percent_decode(url: &str) -> String {
+    let bytes = url.as_bytes();
+    let mut result = vec![];
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let hex = url.get(pos + 1..pos + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[pos], hex) {
+            (b'%', Some(byte)) => {
+                result.push(byte);
+                pos += 3;
+            }
+            (byte, _) => {
+                result.push(byte);
+                pos += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&result).to_string()
+}
+
+/// 将资源 URL 解析为本地路径: 以 '/' 开头的相对于 `root`, 其余相对于入口文件所在的目录; 远程资源返回 `None`
+fn resolve_url(url: &str, root: &Path, base: &Path) -> Option<PathBuf> {
+    let url = url.trim();
+    if url.is_empty() || url.starts_with("//") || url.contains("://") || url.starts_with("data:") {
+        return None;
+    }
+
+    let path = percent_decode(url.split(['?', '#']).next().unwrap_or_default());
+    match path.strip_prefix('/') {
+        Some(path) => Some(root.join(path)),
+        None => Some(base.join(path)),
+    }
+}
+
+/// 更新已有 integrity 属性的值: 只替换所选算法的值 (多个时只保留一个), 其他算法的值保持原样; 没有所选算法的值时追加
+fn replace_integrity(existing: &str, algorithm: &str, integrity: &str) -> String {
+    let mut values = vec![];
+    let mut replaced = false;
+    for value in existing.split_ascii_whitespace() {
+        let prefix = value.split_once('-').map_or("", |(prefix, _)| prefix);
+        if !prefix.eq_ignore_ascii_case(algorithm) {
+            values.push(value);
+        } else if !replaced {
+            values.push(integrity);
+            replaced = true;
+        }
+    }
+    if !replaced {
+        values.push(integrity);
+    }
+    values.join(" ")
+}
+
+/// 一个资源的处理结果
+#[derive(Debug, PartialEq)]
+enum Status {
+    /// 已有的 integrity 属性与文件一致
+    Ok,
+    /// 缺少 integrity 属性
+    Missing,
+    /// integrity 属性与文件不一致
+    Outdated,
+    /// 远程资源, 跳过
+    Remote,
+    /// 本地文件不存在或无法读取
+    NotFound(String),
+}
+
+/// 入口文件引用的一个资源
+struct Resource {
+    url: String,
+    /// 根据本地文件计算的 integrity 值 (远程或不存在的资源为空)
+    integrity: String,
+    status: Status,
+}
+
+pub struct SriImpl {}
+
+impl SriImpl {
+    /// 计算入口文件中所有本地资源的 integrity 值, 返回各资源的结果及写回 integrity 属性后的 HTML
+    fn process(html: &str, root: &Path, base: &Path, algorithm: &str) -> Result<(Vec<Resource>, String), String> {
+        let mut results = vec![];
+        // (替换范围, 替换内容), 按位置从后往前应用
+        let mut edits: Vec<(Range<usize>, String)> = vec![];
+
+        for tag in scan_tags(html) {
+            let url = match resource_url(&tag) {
+                Some(url) => url.to_string(),
+                None => continue,
+            };
+            let path = match resolve_url(&url, root, base) {
+                Some(path) => path,
+                None => {
+                    results.push(Resource { url, integrity: String::new(), status: Status::Remote });
+                    continue;
+                }
+            };
+            let digest = match digest_file(algorithm, &path, None) {
+                Ok(digest) => digest,
+                Err(err) => {
+                    let status = Status::NotFound(format!("{}: {err}", path.display()));
+                    results.push(Resource { url, integrity: String::new(), status });
+                    continue;
+                }
+            };
+
+            let integrity = DigestEncoding::Sri.encode(algorithm, &digest)?;
+            let status = match tag.attribute("integrity") {
+                // 已有的 integrity 可能同时包含多个算法的值 (以空白分隔)
+                Some(existing) if existing.value.split_ascii_whitespace().any(|value| value == integrity) => Status::Ok,
+                Some(existing) => {
+                    let value = replace_integrity(&existing.value, algorithm, &integrity);
+                    edits.push((existing.range.clone(), format!("integrity=\"{value}\"")));
+                    Status::Outdated
+                }
+                None => {
+                    edits.push((tag.insert_at..tag.insert_at, format!(" integrity=\"{integrity}\"")));
+                    Status::Missing
+                }
+            };
+            results.push(Resource { url, integrity, status });
+        }
+
+        let mut output = html.to_string();
+        for (range, text) in edits.into_iter().rev() {
+            output.replace_range(range, &text);
+        }
+
+        Ok((results, output))
+    }
+
+    /// 处理 Command::Sri 子命令
+    pub fn handle(options: SriOptions) {
+        let SriOptions { root, entry, algorithm, write } = options;
+        println!("[Commands::Sri] root = '{root}', entry = '{entry}', algorithm = '{algorithm}', write = '{write}'");
+
+        let algorithm = algorithm.to_lowercase();
+        if !["sha256", "sha384", "sha512"].contains(&algorithm.as_str()) {
+            return println!("Error: SRI only supports sha256, sha384 and sha512, got '{algorithm}'");
+        }
+        let root = PathBuf::from(root);
+        let entry = root.join(entry);
+        let html = match fs::read_to_string(&entry) {
+            Ok(html) => html,
+            Err(err) => return println!("Error: Invalid entry '{}' ({err})", entry.display()),
+        };
+        let base = entry.parent().map_or(PathBuf::new(), |parent| parent.to_path_buf());
+
+        let (results, output) = match SriImpl::process(&html, &root, &base, &algorithm) {
+            Ok(result) => result,
+            Err(err) => return println!("Error: {err}"),
+        };
+
+        let (mut ok, mut changed, mut remote, mut not_found) = (0, 0, 0, 0);
+        for Resource { status, .. } in &results {
+            match status {
+                Status::Ok => ok += 1,
+                Status::Missing | Status::Outdated => changed += 1,
+                Status::Remote => remote += 1,
+                Status::NotFound(_) => not_found += 1,
+            }
+        }
+        // 有资源缺失时命令会失败, 此时不写入文件
+        let write = write && not_found == 0;
+
+        for Resource { url, integrity, status } in &results {
+            match (status, write) {
+                (Status::Ok, _) => println!("{url}: OK ({integrity})"),
+                (Status::Missing, true) => println!("{url}: ADDED ({integrity})"),
+                (Status::Missing, false) => println!("{url}: MISSING ({integrity})"),
+                (Status::Outdated, true) => println!("{url}: UPDATED ({integrity})"),
+                (Status::Outdated, false) => println!("{url}: OUTDATED ({integrity})"),
+                (Status::Remote, _) => println!("{url}: SKIPPED (remote resource)"),
+                (Status::NotFound(err), _) => println!("{url}: NOT FOUND ({err})"),
+            }
+        }
+        println!("Summary: {ok} OK, {changed} {}, {remote} skipped, {not_found} not found", if write { "written" } else { "missing or outdated" });
+
+        if write && changed > 0 {
+            if let Err(err) = fs::write(&entry, output) {
+                println!("Error: {err}");
+                std::process::exit(1);
+            }
+        }
+
+        match (not_found, changed, write) {
+            (0, 0, _) => println!("Ok"),
+            (0, _, true) => println!("Ok: {changed} integrity attribute(s) written to '{}'", entry.display()),
+            (0, _, false) => {
+                println!("Error: {changed} integrity attribute(s) missing or outdated (use '--write' to update them)");
+                std::process::exit(1);
+            }
+            _ => {
+                println!("Error: {not_found} resource(s) not found (nothing is written)");
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod unit_test {
+    use super::*;
+
+    #[test]
+    fn scan_tags_test() {
+        let html = "<!-- <script src=\"x.js\"> --><LINK rel='preload stylesheet' HREF=/a.css /><script>if (a<b) {}</script><script defer src=b.js>";
+        let tags = scan_tags(html);
+        let urls: Vec<_> = tags.iter().filter_map(resource_url).collect();
+        assert_eq!(urls, vec!["/a.css", "b.js"]);
+        assert_eq!(&html[..tags[0].insert_at], "<!-- <script src=\"x.js\"> --><LINK rel='preload stylesheet' HREF=/a.css");
+
+        // 无引号的值紧跟 "/>", 值中间的 '/' 仍然保留
+        let html = "<script src=/js/a.js/></script><link rel=stylesheet href=b.css/>";
+        let tags = scan_tags(html);
+        let urls: Vec<_> = tags.iter().filter_map(resource_url).collect();
+        assert_eq!(urls, vec!["/js/a.js", "b.css"]);
+        assert_eq!(&html[tags[0].insert_at..], "/></script><link rel=stylesheet href=b.css/>");
+    }
+
+    #[test]
+    fn resolve_url_test() {
+        let (root, base) = (Path::new("dist"), Path::new("dist/app"));
+        assert_eq!(resolve_url("/js/a%20b.js?v=1#x", root, base), Some(PathBuf::from("dist/js/a b.js")));
+        assert_eq!(resolve_url("./main.css", root, base), Some(PathBuf::from("dist/app/./main.css")));
+        assert_eq!(resolve_url("https://cdn.example.com/a.js", root, base), None);
+        assert_eq!(resolve_url("//cdn.example.com/a.js", root, base), None);
+    }
+
+    #[test]
+    fn process_test() {
+        let dir = std::env::temp_dir().join("rstool_sri_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.js"), "alert('Hello, world.');").unwrap();
+
+        // https://www.w3.org/TR/SRI/ 中的示例
+        let integrity = "sha384-H8BRh8j48O9oYatfu5AZzq6A9RINhZO5H16dQZngK7T62em8MUt1FLm52t+eX6xO";
+        let html = "<script src=\"a.js\"></script>\n<link rel=stylesheet href=a.js integrity=\"sha384-old\" />\n<script src=\"missing.js\"></script>";
+        let (results, output) = SriImpl::process(html, &dir, &dir, "sha384").unwrap();
+        assert_eq!(results[0].status, Status::Missing);
+        assert_eq!(results[1].status, Status::Outdated);
+        assert!(matches!(results[2].status, Status::NotFound(_)));
+        assert_eq!(output, format!("<script src=\"a.js\" integrity=\"{integrity}\"></script>\n<link rel=stylesheet href=a.js integrity=\"{integrity}\" />\n<script src=\"missing.js\"></script>"));
+
+        let (results, _) = SriImpl::process(&output, &dir, &dir, "sha384").unwrap();
+        assert!(results[..2].iter().all(|resource| resource.status == Status::Ok));
+
+        // 只替换所选算法的值, 其他算法的值保留
+        let html = "<script src=a.js integrity=\"sha512-other  sha384-old sha384-older\"></script>";
+        let (results, output) = SriImpl::process(html, &dir, &dir, "sha384").unwrap();
+        assert_eq!(results[0].status, Status::Outdated);
+        assert_eq!(output, format!("<script src=a.js integrity=\"sha512-other {integrity}\"></script>"));
+        assert_eq!(replace_integrity("sha256-a", "sha384", "sha384-b"), "sha256-a sha384-b");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}